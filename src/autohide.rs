@@ -0,0 +1,261 @@
+// ============ autohide.rs ============
+use gtk4 as gtk;
+use gtk4::glib;
+use gtk4::prelude::*;
+use gtk4_layer_shell::{Edge, LayerShell};
+use std::{cell::Cell, cell::RefCell, rc::Rc, time::Duration};
+
+/// Thickness of the trigger window along the screen edge
+const TRIGGER_SIZE: i32 = 2;
+
+thread_local! {
+    // Keeps trigger windows invisible unless the user styles them
+    static TRIGGER_STYLE: gtk::CssProvider = {
+        let provider = gtk::CssProvider::new();
+        provider.load_from_data(".autohide-trigger { background: transparent; }");
+        if let Some(display) = gtk::gdk::Display::default() {
+            gtk::style_context_add_provider_for_display(
+                &display,
+                &provider,
+                gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+            );
+        }
+        provider
+    };
+}
+
+/// Keeps a bar hidden until the pointer touches its screen edge.
+///
+/// A thin trigger window stays on the edge while the bar is open. Entering
+/// it slides the bar in through a `gtk::Revealer`, leaving the bar slides
+/// it out again after the configured delay. Disabling autohide pins the
+/// bar like a normal one until it is enabled again.
+#[derive(Clone)]
+pub struct AutoHide {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    window: gtk::Window,
+    revealer: gtk::Revealer,
+    trigger: gtk::Window,
+    delay: Duration,
+    reserve_space: bool,
+    enabled: Cell<bool>,
+    open: Cell<bool>,
+    hovered: Cell<bool>,
+    hide_source: RefCell<Option<glib::SourceId>>,
+}
+
+impl AutoHide {
+    /// Move the content of `window` into a revealer and create the trigger
+    /// window on the same edge and `monitor`.
+    pub fn new(
+        app: &gtk::Application,
+        window: &gtk::Window,
+        bar_config: &crate::config::BarConfig,
+        monitor: Option<&gtk::gdk::Monitor>,
+    ) -> Self {
+        TRIGGER_STYLE.with(|_| ());
+
+        let vertical = matches!(bar_config.position.as_str(), "left" | "right");
+
+        // Slide in from the edge the bar sits on
+        let transition = bar_config
+            .transition
+            .as_deref()
+            .and_then(crate::modules::transition_type)
+            .unwrap_or(match bar_config.position.as_str() {
+                "bottom" => gtk::RevealerTransitionType::SlideUp,
+                "left" => gtk::RevealerTransitionType::SlideRight,
+                "right" => gtk::RevealerTransitionType::SlideLeft,
+                _ => gtk::RevealerTransitionType::SlideDown,
+            });
+
+        let revealer = gtk::Revealer::new();
+        revealer.set_transition_type(transition);
+        revealer.set_transition_duration(
+            bar_config
+                .transition_duration
+                .map_or(250, |duration| duration.as_millis() as u32),
+        );
+        let content = window.child();
+        window.set_child(None::<&gtk::Widget>);
+        revealer.set_child(content.as_ref());
+        window.set_child(Some(&revealer));
+        window.set_exclusive_zone(0);
+
+        let trigger = gtk::Window::new();
+        trigger.init_layer_shell();
+        trigger.set_namespace(Some(&format!("{}-trigger", bar_config.namespace)));
+        trigger.set_layer(window.layer());
+        trigger.set_monitor(monitor);
+        trigger.set_exclusive_zone(0);
+        for edge in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+            trigger.set_anchor(edge, window.is_anchor(edge));
+        }
+        let (width, height) = window.default_size();
+        if vertical {
+            trigger.set_default_size(TRIGGER_SIZE, height);
+        } else {
+            trigger.set_default_size(width, TRIGGER_SIZE);
+        }
+        trigger.set_application(Some(app));
+        trigger.add_css_class("autohide-trigger");
+
+        let autohide = Self {
+            inner: Rc::new(Inner {
+                window: window.clone(),
+                revealer: revealer.clone(),
+                trigger: trigger.clone(),
+                delay: bar_config.hide_delay,
+                reserve_space: bar_config.reserve_space,
+                enabled: Cell::new(true),
+                open: Cell::new(false),
+                hovered: Cell::new(false),
+                hide_source: RefCell::new(None),
+            }),
+        };
+
+        // Touching the edge reveals the bar
+        let trigger_motion = gtk::EventControllerMotion::new();
+        trigger_motion.connect_enter({
+            let autohide = autohide.clone();
+            move |_, _, _| autohide.reveal()
+        });
+        trigger_motion.connect_leave({
+            let autohide = autohide.clone();
+            move |_| autohide.schedule_hide()
+        });
+        trigger.add_controller(trigger_motion);
+
+        // Stay revealed while the pointer is on the bar
+        let bar_motion = gtk::EventControllerMotion::new();
+        bar_motion.connect_enter({
+            let autohide = autohide.clone();
+            move |_, _, _| {
+                autohide.inner.hovered.set(true);
+                autohide.cancel_hide();
+            }
+        });
+        bar_motion.connect_leave({
+            let autohide = autohide.clone();
+            move |_| {
+                autohide.inner.hovered.set(false);
+                autohide.schedule_hide();
+            }
+        });
+        window.add_controller(bar_motion);
+
+        // Unmap the bar once it slid out so it no longer takes input
+        revealer.connect_child_revealed_notify({
+            let window = window.clone();
+            move |revealer| {
+                if !revealer.is_child_revealed() && !revealer.reveals_child() {
+                    window.set_visible(false);
+                }
+            }
+        });
+
+        // Without an output the compositor places the bar, move the trigger
+        // to whichever monitor that is
+        if monitor.is_none() {
+            window.connect_realize({
+                let trigger = trigger.clone();
+                move |window| {
+                    let Some(surface) = window.surface() else {
+                        return;
+                    };
+                    let trigger = trigger.clone();
+                    surface.connect_enter_monitor(move |_, monitor| {
+                        if trigger.monitor().as_ref() != Some(monitor) {
+                            trigger.set_monitor(Some(monitor));
+                        }
+                    });
+                }
+            });
+        }
+
+        autohide
+    }
+
+    /// Open the bar: show the trigger and peek the bar until the delay passes.
+    pub fn open(&self) {
+        self.inner.open.set(true);
+        if self.inner.enabled.get() {
+            self.inner.trigger.present();
+        }
+        self.reveal();
+    }
+
+    /// Close the bar together with its trigger.
+    pub fn close(&self) {
+        self.inner.open.set(false);
+        self.cancel_hide();
+        self.inner.trigger.set_visible(false);
+        self.inner.revealer.set_reveal_child(false);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.inner.open.get()
+    }
+
+    /// Switch between autohide and a pinned bar.
+    pub fn toggle(&self) {
+        let enabled = !self.inner.enabled.get();
+        self.inner.enabled.set(enabled);
+
+        if enabled {
+            self.inner.window.set_exclusive_zone(0);
+            if self.inner.open.get() {
+                self.inner.trigger.present();
+            }
+            self.schedule_hide();
+        } else {
+            if self.inner.reserve_space {
+                self.inner.window.auto_exclusive_zone_enable();
+            }
+            self.inner.trigger.set_visible(false);
+            if self.inner.open.get() {
+                self.reveal();
+            }
+        }
+    }
+
+    pub fn destroy(&self) {
+        self.cancel_hide();
+        self.inner.trigger.destroy();
+    }
+
+    fn reveal(&self) {
+        if !self.inner.open.get() {
+            return;
+        }
+        self.cancel_hide();
+        self.inner.window.present();
+        self.inner.revealer.set_reveal_child(true);
+        self.schedule_hide();
+    }
+
+    fn schedule_hide(&self) {
+        self.cancel_hide();
+        if !self.inner.enabled.get() || self.inner.hovered.get() {
+            return;
+        }
+
+        let autohide = self.clone();
+        let source = glib::timeout_add_local_once(self.inner.delay, move || {
+            autohide.inner.hide_source.borrow_mut().take();
+            if autohide.inner.enabled.get() && !autohide.inner.hovered.get() {
+                autohide.inner.revealer.set_reveal_child(false);
+            }
+        });
+        *self.inner.hide_source.borrow_mut() = Some(source);
+    }
+
+    fn cancel_hide(&self) {
+        if let Some(source) = self.inner.hide_source.borrow_mut().take() {
+            source.remove();
+        }
+    }
+}
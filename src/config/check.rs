@@ -0,0 +1,449 @@
+// ============ config/check.rs ============
+use super::{
+    ActiveWindowConfig, AudioConfig, BarConfig, BatteryConfig, BoxConfig, ClockConfig, Config,
+    CustomModule, GeneralConfig, MicrophoneConfig, MprisConfig, NetworkConfig, RevealerConfig,
+    SliderModule, TrayConfig, WorkspacesConfig,
+};
+use serde::de::{self, DeserializeOwned, Visitor};
+use std::{collections::HashSet, fs, ops::Range, path::Path};
+use toml::{
+    Spanned,
+    de::{DeTable, DeValue},
+};
+
+/// Module prefixes and the config section holding their tables.
+const SECTIONS: [(&str, &str); 4] = [
+    ("custom/", "custom_modules"),
+    ("sliders/", "sliders"),
+    ("box/", "boxes"),
+    ("revealer/", "revealers"),
+];
+
+const POSITIONS: [&str; 4] = ["top", "bottom", "left", "right"];
+const LAYERS: [&str; 4] = ["background", "bottom", "top", "overlay"];
+const MODES: [&str; 2] = ["normal", "autohide"];
+const FULLSCREEN_ACTIONS: [&str; 3] = ["none", "hide", "lower"];
+const ORIENTATIONS: [&str; 2] = ["horizontal", "vertical"];
+const RETURN_TYPES: [&str; 2] = ["text", "json"];
+const TRANSITIONS: [&str; 5] = [
+    "slide_left",
+    "slide_right",
+    "slide_up",
+    "slide_down",
+    "crossfade",
+];
+
+/// Validate the config at `config_path` and its stylesheet, printing every
+/// problem as `file:line:column`. Returns the exit code for `riftbar check`.
+pub fn check(config_path: &Path) -> i32 {
+    let content = match fs::read_to_string(config_path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!(
+                "{}: error: Failed to read config: {}",
+                config_path.display(),
+                e
+            );
+            return 1;
+        }
+    };
+
+    let mut checker = Checker {
+        path: config_path,
+        content: &content,
+        errors: 0,
+        warnings: 0,
+    };
+    checker.run();
+
+    if let Err(e) = super::check_scss() {
+        eprintln!("{}", e);
+        checker.errors += 1;
+    }
+
+    if checker.errors + checker.warnings == 0 {
+        println!("{}: config OK", config_path.display());
+        0
+    } else {
+        eprintln!(
+            "{} error(s), {} warning(s)",
+            checker.errors, checker.warnings
+        );
+        1
+    }
+}
+
+struct Checker<'a> {
+    path: &'a Path,
+    content: &'a str,
+    errors: usize,
+    warnings: usize,
+}
+
+impl Checker<'_> {
+    fn run(&mut self) {
+        let root = match DeTable::parse(self.content) {
+            Ok(root) => root,
+            Err(e) => {
+                self.error(e.span().unwrap_or_default(), e.message());
+                return;
+            }
+        };
+        let root = root.get_ref();
+
+        if let Err(e) = toml::from_str::<Config>(self.content) {
+            self.error(e.span().unwrap_or_default(), e.message());
+        }
+
+        self.check_keys(root);
+        self.check_modules(root);
+        self.check_cycles(root);
+        self.check_values(root);
+    }
+
+    fn report(&mut self, span: Range<usize>, level: &str, message: &str) {
+        let before = &self.content[..span.start.min(self.content.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        eprintln!(
+            "{}:{}:{}: {}: {}",
+            self.path.display(),
+            line,
+            column,
+            level,
+            message.trim_end()
+        );
+    }
+
+    fn error(&mut self, span: Range<usize>, message: &str) {
+        self.errors += 1;
+        self.report(span, "error", message);
+    }
+
+    fn warning(&mut self, span: Range<usize>, message: &str) {
+        self.warnings += 1;
+        self.report(span, "warning", message);
+    }
+
+    /// Warn about keys that serde silently ignores.
+    fn check_keys(&mut self, root: &DeTable) {
+        for (key, value) in root.iter() {
+            let name = key.get_ref().as_ref();
+
+            if let Some((base, _)) = name.split_once('#') {
+                match instance_fields(base) {
+                    Some(fields) => self.check_table(value, name, fields),
+                    None => self.warning(
+                        key.span(),
+                        &format!("instance `{}` of unknown module `{}`", name, base),
+                    ),
+                }
+                continue;
+            }
+
+            match name {
+                "general" => self.check_table(value, name, fields::<GeneralConfig>()),
+                "workspaces" => self.check_table(value, name, fields::<WorkspacesConfig>()),
+                "active_window" => self.check_table(value, name, fields::<ActiveWindowConfig>()),
+                "network" => self.check_table(value, name, fields::<NetworkConfig>()),
+                "mpris" => self.check_table(value, name, fields::<MprisConfig>()),
+                "battery" => self.check_table(value, name, fields::<BatteryConfig>()),
+                "audio" => self.check_table(value, name, fields::<AudioConfig>()),
+                "microphone" => self.check_table(value, name, fields::<MicrophoneConfig>()),
+                "clock" => self.check_table(value, name, fields::<ClockConfig>()),
+                "tray" => self.check_table(value, name, fields::<TrayConfig>()),
+                "bars" => self.check_entries(value, name, fields::<BarConfig>()),
+                "custom_modules" => self.check_entries(value, name, fields::<CustomModule>()),
+                "sliders" => self.check_entries(value, name, fields::<SliderModule>()),
+                "boxes" => self.check_entries(value, name, fields::<BoxConfig>()),
+                "revealers" => self.check_entries(value, name, fields::<RevealerConfig>()),
+                _ => self.warning(key.span(), &format!("unknown key `{}`", name)),
+            }
+        }
+    }
+
+    fn check_table(&mut self, value: &Spanned<DeValue>, section: &str, fields: &[&str]) {
+        let Some(table) = value.get_ref().as_table() else {
+            return;
+        };
+
+        for (key, _) in table.iter() {
+            let name = key.get_ref().as_ref();
+            if !fields.contains(&name) {
+                self.warning(
+                    key.span(),
+                    &format!("unknown key `{}` in [{}]", name, section),
+                );
+            }
+        }
+    }
+
+    fn check_entries(&mut self, value: &Spanned<DeValue>, section: &str, fields: &[&str]) {
+        let Some(table) = value.get_ref().as_table() else {
+            return;
+        };
+
+        for (key, entry) in table.iter() {
+            let section = format!("{}.{}", section, key.get_ref());
+            self.check_table(entry, &section, fields);
+        }
+    }
+
+    /// Every module name in bars, boxes and revealers must resolve.
+    fn check_modules(&mut self, root: &DeTable) {
+        for (name, span) in module_refs(root) {
+            let base = name.split('#').next().unwrap_or_default();
+
+            let section = SECTIONS.iter().find_map(|(prefix, section)| {
+                base.strip_prefix(prefix)
+                    .map(|key| (*prefix, *section, key))
+            });
+
+            match section {
+                Some((prefix, section, key)) => {
+                    if entry(root, section, key).is_none() {
+                        self.error(
+                            span,
+                            &format!(
+                                "`{}{}` is not defined, expected a [{}.{}] table",
+                                prefix, key, section, key
+                            ),
+                        );
+                    }
+                }
+                None => {
+                    if !crate::modules::registry().contains(base) {
+                        self.error(span, &format!("unknown module `{}`", name));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Boxes and revealers must not contain themselves.
+    fn check_cycles(&mut self, root: &DeTable) {
+        let mut done = HashSet::new();
+
+        for (prefix, section) in [("box/", "boxes"), ("revealer/", "revealers")] {
+            let Some(table) = get(root, section).and_then(|value| value.get_ref().as_table())
+            else {
+                continue;
+            };
+
+            for (key, _) in table.iter() {
+                let mut path = Vec::new();
+                self.visit(
+                    root,
+                    format!("{}{}", prefix, key.get_ref()),
+                    &mut path,
+                    &mut done,
+                );
+            }
+        }
+    }
+
+    fn visit(
+        &mut self,
+        root: &DeTable,
+        node: String,
+        path: &mut Vec<String>,
+        done: &mut HashSet<String>,
+    ) {
+        if done.contains(&node) {
+            return;
+        }
+        path.push(node.clone());
+
+        for (child, span) in container_children(root, &node) {
+            if let Some(start) = path.iter().position(|name| *name == child) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(child.clone());
+                self.error(
+                    span,
+                    &format!("`{}` contains itself: {}", child, cycle.join(" -> ")),
+                );
+            } else {
+                self.visit(root, child, path, done);
+            }
+        }
+
+        path.pop();
+        done.insert(node);
+    }
+
+    /// String options that only accept a fixed set of values.
+    fn check_values(&mut self, root: &DeTable) {
+        let checks: [(&str, &str, &[&str]); 9] = [
+            ("bars", "position", &POSITIONS),
+            ("bars", "layer", &LAYERS),
+            ("bars", "mode", &MODES),
+            ("bars", "on_fullscreen", &FULLSCREEN_ACTIONS),
+            ("bars", "transition", &TRANSITIONS),
+            ("boxes", "orientation", &ORIENTATIONS),
+            ("revealers", "orientation", &ORIENTATIONS),
+            ("revealers", "transition", &TRANSITIONS),
+            ("custom_modules", "return_type", &RETURN_TYPES),
+        ];
+
+        for (section, option, allowed) in checks {
+            let Some(table) = get(root, section).and_then(|value| value.get_ref().as_table())
+            else {
+                continue;
+            };
+
+            for (_, entry) in table.iter() {
+                let Some(value) = entry
+                    .get_ref()
+                    .as_table()
+                    .and_then(|entry| get(entry, option))
+                else {
+                    continue;
+                };
+
+                if let Some(text) = value.get_ref().as_str()
+                    && !allowed.contains(&text)
+                {
+                    self.error(
+                        value.span(),
+                        &format!(
+                            "invalid {} `{}`, expected one of: {}",
+                            option,
+                            text,
+                            allowed.join(", ")
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn get<'a, 'i>(table: &'a DeTable<'i>, key: &str) -> Option<&'a Spanned<DeValue<'i>>> {
+    table
+        .iter()
+        .find(|(name, _)| name.get_ref() == key)
+        .map(|(_, value)| value)
+}
+
+fn entry<'a, 'i>(root: &'a DeTable<'i>, section: &str, key: &str) -> Option<&'a DeTable<'i>> {
+    get(root, section)?
+        .get_ref()
+        .as_table()
+        .and_then(|table| get(table, key))?
+        .get_ref()
+        .as_table()
+}
+
+/// Module names listed in `table` under `key`, with their location.
+fn names_in(table: &DeTable, key: &str) -> Vec<(String, Range<usize>)> {
+    let Some(array) = get(table, key).and_then(|value| value.get_ref().as_array()) else {
+        return Vec::new();
+    };
+
+    array
+        .into_iter()
+        .filter_map(|value| Some((value.get_ref().as_str()?.to_string(), value.span())))
+        .collect()
+}
+
+/// Every module referenced from a bar, box or revealer.
+fn module_refs(root: &DeTable) -> Vec<(String, Range<usize>)> {
+    let mut refs = Vec::new();
+
+    for (section, keys) in [
+        (
+            "bars",
+            &["modules_left", "modules_center", "modules_right"][..],
+        ),
+        ("boxes", &["modules"][..]),
+        ("revealers", &["modules"][..]),
+    ] {
+        let Some(table) = get(root, section).and_then(|value| value.get_ref().as_table()) else {
+            continue;
+        };
+
+        for (_, entry) in table.iter() {
+            if let Some(entry) = entry.get_ref().as_table() {
+                for key in keys {
+                    refs.extend(names_in(entry, key));
+                }
+            }
+        }
+    }
+
+    refs
+}
+
+/// Boxes and revealers directly inside the box or revealer `node`.
+fn container_children(root: &DeTable, node: &str) -> Vec<(String, Range<usize>)> {
+    let (section, key) = if let Some(key) = node.strip_prefix("box/") {
+        ("boxes", key)
+    } else if let Some(key) = node.strip_prefix("revealer/") {
+        ("revealers", key)
+    } else {
+        return Vec::new();
+    };
+
+    let Some(table) = entry(root, section, key) else {
+        return Vec::new();
+    };
+
+    names_in(table, "modules")
+        .into_iter()
+        .map(|(name, span)| (name.split('#').next().unwrap_or_default().to_string(), span))
+        .filter(|(name, _)| name.starts_with("box/") || name.starts_with("revealer/"))
+        .collect()
+}
+
+/// Known keys of a `["module#instance"]` table.
+fn instance_fields(module: &str) -> Option<&'static [&'static str]> {
+    Some(match module {
+        "clock" => fields::<ClockConfig>(),
+        "tray" => fields::<TrayConfig>(),
+        "hyprland/workspaces" => fields::<WorkspacesConfig>(),
+        "active_window" => fields::<ActiveWindowConfig>(),
+        "mpris" => fields::<MprisConfig>(),
+        "network" => fields::<NetworkConfig>(),
+        "battery" => fields::<BatteryConfig>(),
+        "audio" => fields::<AudioConfig>(),
+        "microphone" => fields::<MicrophoneConfig>(),
+        _ if module.starts_with("custom/") => fields::<CustomModule>(),
+        _ if module.starts_with("sliders/") => fields::<SliderModule>(),
+        _ if module.starts_with("box/") => fields::<BoxConfig>(),
+        _ if module.starts_with("revealer/") => fields::<RevealerConfig>(),
+        _ => return None,
+    })
+}
+
+/// Field names of a config struct, read from its `Deserialize` impl.
+fn fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    struct Fields<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> de::Deserializer<'de> for Fields<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("fields collected"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(Fields(&mut fields));
+    fields
+}
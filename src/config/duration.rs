@@ -0,0 +1,115 @@
+// ============ config/duration.rs ============
+use serde::de::{self, Deserializer, Visitor};
+use std::{fmt, time::Duration};
+
+/// Parse a duration like `"500ms"`, `"2s"`, `"1.5s"`, `"5m"` or `"1h"`.
+/// Zero is allowed here, `DurationVisitor` decides whether it is valid.
+pub fn parse(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .ok_or_else(|| format!("missing unit in `{}`, use ms, s, m or h", text))?;
+    let (value, unit) = text.split_at(split);
+
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid duration `{}`", text))?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        unit => {
+            return Err(format!(
+                "unknown unit `{}` in `{}`, use ms, s, m or h",
+                unit, text
+            ));
+        }
+    };
+
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("duration `{}` is too long", text))
+}
+
+/// A duration string, or a bare number counted in `unit`, the unit the
+/// option used before duration strings existed.
+struct DurationVisitor {
+    unit: Duration,
+    /// Accept zero, for delays and animations that can be turned off.
+    allow_zero: bool,
+}
+
+impl DurationVisitor {
+    fn check<E: de::Error>(&self, duration: Duration) -> Result<Duration, E> {
+        if duration.is_zero() && !self.allow_zero {
+            return Err(E::custom("duration must be greater than zero"));
+        }
+        Ok(duration)
+    }
+}
+
+impl Visitor<'_> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a duration like \"500ms\", \"2s\" or \"5m\", or a number")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Duration, E> {
+        let duration = self
+            .unit
+            .checked_mul(value.try_into().map_err(E::custom)?)
+            .ok_or_else(|| E::custom("duration is too long"))?;
+        self.check(duration)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Duration, E> {
+        let value = u64::try_from(value).map_err(|_| E::custom("duration can't be negative"))?;
+        self.visit_u64(value)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Duration, E> {
+        self.check(parse(value).map_err(E::custom)?)
+    }
+}
+
+/// `deserialize_with` for options whose bare numbers are seconds.
+pub fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    deserializer.deserialize_any(DurationVisitor {
+        unit: Duration::from_secs(1),
+        allow_zero: false,
+    })
+}
+
+/// `deserialize_with` for options whose bare numbers are milliseconds.
+pub fn milliseconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    deserializer.deserialize_any(DurationVisitor {
+        unit: Duration::from_millis(1),
+        allow_zero: false,
+    })
+}
+
+/// Like `milliseconds`, for options where zero turns a delay or an
+/// animation off.
+pub fn milliseconds_or_zero<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Duration, D::Error> {
+    deserializer.deserialize_any(DurationVisitor {
+        unit: Duration::from_millis(1),
+        allow_zero: true,
+    })
+}
+
+/// `deserialize_with` for optional durations, bare numbers are seconds.
+pub fn optional_seconds<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    seconds(deserializer).map(Some)
+}
+
+/// `deserialize_with` for optional durations that may be zero, bare numbers
+/// are milliseconds.
+pub fn optional_milliseconds_or_zero<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    milliseconds_or_zero(deserializer).map(Some)
+}
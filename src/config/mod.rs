@@ -1,6 +1,9 @@
 // ============ mod.rs ============
+pub mod check;
+pub mod duration;
 pub mod scss;
 pub mod toml;
 
+pub use check::check;
 pub use scss::*;
 pub use toml::*;
@@ -1,9 +1,13 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-pub fn load_css_string() -> Option<String> {
+fn style_dir() -> Option<PathBuf> {
     let home = std::env::var("HOME").ok()?;
-    let config_dir = PathBuf::from(home).join(".config/riftbar");
+    Some(PathBuf::from(home).join(".config/riftbar"))
+}
+
+pub fn load_css_string() -> Option<String> {
+    let config_dir = style_dir()?;
 
     let scss_path = config_dir.join("style.scss");
     let css_path = config_dir.join("style.css");
@@ -31,3 +35,115 @@ pub fn load_css_string() -> Option<String> {
     // No style file is not an error
     None
 }
+
+/// Compile `style.scss` like `load_css_string` does, returning the error
+/// with its `file:line:column` location if it fails.
+pub fn check_scss() -> Result<(), String> {
+    let Some(config_dir) = style_dir() else {
+        return Ok(());
+    };
+
+    let scss_path = config_dir.join("style.scss");
+    if !scss_path.exists() {
+        return Ok(());
+    }
+
+    let options = grass::Options::default().load_path(&config_dir);
+    match grass::from_path(&scss_path, &options) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(match err.kind() {
+            grass::ErrorKind::ParseError { message, loc, .. } => format!(
+                "{}:{}:{}: error: {}",
+                loc.file.name(),
+                loc.begin.line + 1,
+                loc.begin.column + 1,
+                message
+            ),
+            other => format!("{}: error: {:?}", scss_path.display(), other),
+        }),
+    }
+}
+
+/// Every file the stylesheet is built from: both style files, whether they
+/// exist yet or not, and every file pulled in through `@use`/`@import`.
+pub fn style_files() -> Vec<PathBuf> {
+    let Some(config_dir) = style_dir() else {
+        return Vec::new();
+    };
+
+    let mut files = vec![config_dir.join("style.scss"), config_dir.join("style.css")];
+    let mut i = 0;
+    while i < files.len() {
+        let imports = match fs::read_to_string(&files[i]) {
+            Ok(content) => imported_names(&content),
+            Err(_) => Vec::new(),
+        };
+        let dir = files[i].parent().unwrap_or(&config_dir).to_path_buf();
+
+        for name in imports {
+            let resolved =
+                resolve_import(&dir, &name).or_else(|| resolve_import(&config_dir, &name));
+            if let Some(path) = resolved
+                && !files.contains(&path)
+            {
+                files.push(path);
+            }
+        }
+        i += 1;
+    }
+
+    files
+}
+
+/// Names referenced by `@use` and `@import` rules, skipping built-in
+/// `sass:` modules and remote URLs.
+fn imported_names(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+
+    for statement in content.split(';') {
+        let statement = statement.trim_start();
+        let (rule, rest) = if let Some(rest) = statement.strip_prefix("@use") {
+            ("use", rest)
+        } else if let Some(rest) = statement.strip_prefix("@import") {
+            ("import", rest)
+        } else {
+            continue;
+        };
+
+        // `@use` takes a single url, `@import` a comma separated list
+        for (index, part) in rest.split(['"', '\'']).enumerate() {
+            if index % 2 == 0 {
+                continue;
+            }
+            if !part.starts_with("sass:") && !part.contains("://") {
+                names.push(part.to_string());
+            }
+            if rule == "use" {
+                break;
+            }
+        }
+    }
+
+    names
+}
+
+/// Resolve an import the way Sass does: partials, `.scss`/`.css`
+/// extensions and `_index` files of directories.
+fn resolve_import(dir: &Path, name: &str) -> Option<PathBuf> {
+    let path = dir.join(name);
+    let file_name = path.file_name()?.to_string_lossy().into_owned();
+    let parent = path.parent()?;
+
+    let mut candidates = Vec::new();
+    if path.extension().is_some() {
+        candidates.push(path.clone());
+    }
+    for ext in ["scss", "css"] {
+        candidates.push(parent.join(format!("{}.{}", file_name, ext)));
+        candidates.push(parent.join(format!("_{}.{}", file_name, ext)));
+    }
+    candidates.push(path.join("_index.scss"));
+    candidates.push(path.join("index.scss"));
+
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
@@ -1,6 +1,12 @@
 // ============ config/toml.rs ============
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::PathBuf};
+use super::duration;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
@@ -34,6 +40,9 @@ pub struct Config {
     #[serde(default)]
     pub audio: AudioConfig,
 
+    #[serde(default)]
+    pub microphone: MicrophoneConfig,
+
     #[serde(default)]
     pub clock: ClockConfig,
 
@@ -45,6 +54,11 @@ pub struct Config {
 
     #[serde(default)]
     pub revealers: std::collections::HashMap<String, RevealerConfig>,
+
+    /// Tables of named module instances such as `["clock#utc"]`, keyed by
+    /// the full module name. Filled in by `Config::load`.
+    #[serde(skip)]
+    pub instances: std::collections::HashMap<String, toml::Table>,
 }
 
 /* #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -60,6 +74,9 @@ pub struct GeneralConfig {
 
     #[serde(default)]
     pub use_gpu: bool,
+
+    #[serde(default)]
+    pub watch_config: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -76,6 +93,15 @@ pub struct BarConfig {
     #[serde(default = "default_height")]
     pub height: u32,
 
+    #[serde(default)]
+    pub width: Option<u32>,
+
+    #[serde(default)]
+    pub floating: bool,
+
+    #[serde(default)]
+    pub length: Option<u32>,
+
     #[serde(default = "default_spacing")]
     pub spacing: i32,
 
@@ -105,6 +131,46 @@ pub struct BarConfig {
 
     #[serde(default = "default_bool")]
     pub open_on_launch: bool,
+
+    #[serde(default)]
+    pub output: Option<BarOutput>,
+
+    #[serde(default = "BarConfig::default_mode")]
+    pub mode: String,
+
+    #[serde(
+        default = "BarConfig::default_hide_delay",
+        deserialize_with = "duration::milliseconds_or_zero"
+    )]
+    pub hide_delay: Duration,
+
+    #[serde(default)]
+    pub transition: Option<String>,
+
+    #[serde(default, deserialize_with = "duration::optional_milliseconds_or_zero")]
+    pub transition_duration: Option<Duration>,
+
+    #[serde(default = "BarConfig::default_on_fullscreen")]
+    pub on_fullscreen: String,
+}
+
+/// Outputs a bar is opened on: a connector name like `DP-1`, a list of
+/// them, or `*` for every output.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum BarOutput {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl BarOutput {
+    pub fn matches(&self, connector: &str) -> bool {
+        let names = match self {
+            BarOutput::One(name) => std::slice::from_ref(name),
+            BarOutput::Many(names) => names.as_slice(),
+        };
+        names.iter().any(|name| name == "*" || name == connector)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -115,6 +181,14 @@ pub struct CustomModule {
     #[serde(default)]
     pub listen: bool,
 
+    /// Only run `exec` while this command succeeds, the module is hidden otherwise.
+    #[serde(default)]
+    pub exec_if: Option<String>,
+
+    /// Hide the module while `exec` prints nothing.
+    #[serde(default)]
+    pub hide_empty: bool,
+
     #[serde(default = "default_command")]
     pub on_click: String,
 
@@ -130,14 +204,72 @@ pub struct CustomModule {
     #[serde(default = "default_command")]
     pub scroll_down: String,
 
-    #[serde(default = "default_interval")]
-    pub interval: u64,
+    #[serde(default = "default_interval", deserialize_with = "duration::seconds")]
+    pub interval: Duration,
+
+    /// Run `exec` on wall-clock multiples of this duration instead of every
+    /// `interval`, e.g. `"1m"` at the start of every minute.
+    #[serde(default, deserialize_with = "duration::optional_seconds")]
+    pub schedule: Option<Duration>,
+
+    /// Re-run `exec` when `SIGRTMIN+signal` arrives.
+    #[serde(default)]
+    pub signal: Option<u32>,
 
     #[serde(default)]
     pub format: Option<String>,
 
+    /// `text` or `json`, where every output is a Waybar style object.
+    #[serde(default = "CustomModule::default_return_type")]
+    pub return_type: String,
+
+    /// Icons for `{icon}`: a list picked by `percentage`, or a table keyed by `alt`.
+    #[serde(default)]
+    pub format_icons: Option<FormatIcons>,
+
     #[serde(default)]
     pub tooltip: Option<bool>,
+
+    /// Command run when the tooltip is shown, its output replaces `{}` in `tooltip_format`.
+    #[serde(default)]
+    pub tooltip_exec: Option<String>,
+
+    #[serde(default)]
+    pub tooltip_format: Option<String>,
+}
+
+impl CustomModule {
+    fn default_return_type() -> String {
+        String::from("text")
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum FormatIcons {
+    List(Vec<String>),
+    Map(HashMap<String, String>),
+}
+
+impl FormatIcons {
+    /// Icon for the output with `alt` and `percentage`, an `alt` without its
+    /// own icon falls back to `default`.
+    pub fn icon(&self, alt: Option<&str>, percentage: Option<u32>) -> &str {
+        match self {
+            FormatIcons::List(icons) => {
+                if icons.is_empty() {
+                    return "";
+                }
+                let percentage = percentage.unwrap_or(0).min(100) as usize;
+                &icons[(percentage * icons.len() / 100).min(icons.len() - 1)]
+            }
+            FormatIcons::Map(icons) => alt
+                .and_then(|alt| icons.get(alt))
+                .or_else(|| icons.get("default"))
+                .map(String::as_str)
+                .unwrap_or_default(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -157,11 +289,19 @@ pub struct SliderModule {
     #[serde(default = "default_int")]
     pub scroll_step: u32,
 
-    #[serde(default = "default_interval")]
-    pub interval: u64,
+    #[serde(default = "default_interval", deserialize_with = "duration::seconds")]
+    pub interval: Duration,
+
+    /// Re-run `exec` when `SIGRTMIN+signal` arrives.
+    #[serde(default)]
+    pub signal: Option<u32>,
 
     #[serde(default)]
     pub tooltip: Option<bool>,
+
+    /// Tooltip with `{}` replaced by the current value.
+    #[serde(default)]
+    pub tooltip_format: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -201,8 +341,11 @@ pub struct NetworkConfig {
     #[serde(default)]
     pub disconnected_icon: Option<String>,
 
-    #[serde(default = "NetworkConfig::default_interval")]
-    pub interval: u64,
+    #[serde(
+        default = "NetworkConfig::default_interval",
+        deserialize_with = "duration::seconds"
+    )]
+    pub interval: Duration,
 
     #[serde(default)]
     pub interface: Option<String>,
@@ -255,8 +398,11 @@ pub struct MprisConfig {
     #[serde(default = "default_length")]
     pub length_lim: u64,
 
-    #[serde(default = "MprisConfig::default_interval")]
-    pub interval: u64,
+    #[serde(
+        default = "MprisConfig::default_interval",
+        deserialize_with = "duration::milliseconds"
+    )]
+    pub interval: Duration,
 
     #[serde(default = "default_tooltip")]
     pub tooltip: bool,
@@ -279,8 +425,11 @@ pub struct BatteryConfig {
     #[serde(default = "BatteryConfig::not_charging_icon")]
     pub not_charging_icon: String,
 
-    #[serde(default = "BatteryConfig::default_interval")]
-    pub interval: u64,
+    #[serde(
+        default = "BatteryConfig::default_interval",
+        deserialize_with = "duration::seconds"
+    )]
+    pub interval: Duration,
 
     #[serde(default)]
     pub battery: Option<String>,
@@ -309,8 +458,71 @@ pub struct AudioConfig {
     #[serde(default = "AudioConfig::default_muted_icon")]
     pub muted_icon: String,
 
-    #[serde(default = "AudioConfig::default_interval")]
-    pub interval: u64,
+    /// Polling interval of the wpctl/pactl fallback, the native backend
+    /// follows server events instead.
+    #[serde(
+        default = "AudioConfig::default_interval",
+        deserialize_with = "duration::milliseconds"
+    )]
+    pub interval: Duration,
+
+    #[serde(default = "default_tooltip")]
+    pub tooltip: bool,
+
+    #[serde(default = "default_on_click")]
+    pub on_click: String,
+
+    #[serde(default = "default_command")]
+    pub on_click_right: String,
+
+    #[serde(default = "default_command")]
+    pub on_click_middle: String,
+
+    #[serde(default = "AudioConfig::default_on_scroll_up")]
+    pub scroll_up: String,
+
+    #[serde(default = "AudioConfig::default_on_scroll_down")]
+    pub scroll_down: String,
+
+    #[serde(default = "AudioConfig::default_scroll_step")]
+    pub scroll_step: i32,
+
+    /// Highest volume in percent that scrolling and the popovers reach,
+    /// above 100 the signal is amplified.
+    #[serde(default = "AudioConfig::default_max_volume")]
+    pub max_volume: i32,
+
+    /// Right click opens a popover to pick the default devices and set
+    /// their volume instead of running `on_click_right`.
+    #[serde(default)]
+    pub device_popover: bool,
+
+    /// Middle click opens a mixer with the volume of every playback stream
+    /// instead of running `on_click_middle`. Needs the PulseAudio protocol,
+    /// served by pipewire-pulse on PipeWire: with only wpctl available the
+    /// streams can't be listed and `on_click_middle` runs instead.
+    #[serde(default)]
+    pub mixer_popover: bool,
+}
+
+/// The default audio source, configured like `[audio]` with its own icons.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MicrophoneConfig {
+    #[serde(default = "AudioConfig::default_format")]
+    pub format: String,
+
+    #[serde(default = "MicrophoneConfig::default_icons")]
+    pub icons: Vec<String>,
+
+    #[serde(default = "MicrophoneConfig::default_muted_icon")]
+    pub muted_icon: String,
+
+    /// Polling interval of the wpctl/pactl fallback.
+    #[serde(
+        default = "AudioConfig::default_interval",
+        deserialize_with = "duration::milliseconds"
+    )]
+    pub interval: Duration,
 
     #[serde(default = "default_tooltip")]
     pub tooltip: bool,
@@ -332,6 +544,16 @@ pub struct AudioConfig {
 
     #[serde(default = "AudioConfig::default_scroll_step")]
     pub scroll_step: i32,
+
+    /// Highest volume in percent that scrolling and the popovers reach,
+    /// above 100 the signal is amplified.
+    #[serde(default = "AudioConfig::default_max_volume")]
+    pub max_volume: i32,
+
+    /// Right click opens a popover to pick the default devices and set
+    /// their volume instead of running `on_click_right`.
+    #[serde(default)]
+    pub device_popover: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -339,8 +561,11 @@ pub struct ClockConfig {
     #[serde(default = "ClockConfig::default_format")]
     pub format: String,
 
-    #[serde(default = "ClockConfig::default_interval")]
-    pub interval: u64,
+    #[serde(
+        default = "ClockConfig::default_interval",
+        deserialize_with = "duration::milliseconds"
+    )]
+    pub interval: Duration,
 
     #[serde(default = "default_tooltip")]
     pub tooltip: bool,
@@ -356,6 +581,9 @@ pub struct ClockConfig {
 
     #[serde(default = "default_on_click")]
     pub on_click_right: String,
+
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -430,8 +658,8 @@ fn default_namespace() -> String {
 fn default_spacing() -> i32 {
     10
 }
-fn default_interval() -> u64 {
-    1
+fn default_interval() -> Duration {
+    Duration::from_secs(1)
 }
 
 fn default_command() -> String {
@@ -445,6 +673,9 @@ impl Default for BarConfig {
             modules_center: default_modules(),
             modules_right: default_modules(),
             height: default_height(),
+            width: None,
+            floating: false,
+            length: None,
             position: default_layer_and_position(),
             layer: default_layer_and_position(),
             namespace: default_namespace(),
@@ -455,10 +686,30 @@ impl Default for BarConfig {
             margin_bottom: 0,
             margin_left: 0,
             margin_right: 0,
+            output: None,
+            mode: Self::default_mode(),
+            hide_delay: Self::default_hide_delay(),
+            transition: None,
+            transition_duration: None,
+            on_fullscreen: Self::default_on_fullscreen(),
         }
     }
 }
 
+impl BarConfig {
+    fn default_mode() -> String {
+        String::from("normal")
+    }
+
+    fn default_hide_delay() -> Duration {
+        Duration::from_millis(500)
+    }
+
+    fn default_on_fullscreen() -> String {
+        String::from("none")
+    }
+}
+
 impl Default for WorkspacesConfig {
     fn default() -> Self {
         Self {
@@ -545,8 +796,8 @@ impl NetworkConfig {
         ]
     }
 
-    fn default_interval() -> u64 {
-        5
+    fn default_interval() -> Duration {
+        Duration::from_secs(5)
     }
 }
 
@@ -575,8 +826,8 @@ impl MprisConfig {
         "No Media".to_string()
     }
 
-    fn default_interval() -> u64 {
-        100
+    fn default_interval() -> Duration {
+        Duration::from_millis(100)
     }
 
     fn default_tooltip_format() -> String {
@@ -642,8 +893,8 @@ impl BatteryConfig {
         String::from("󱟤")
     }
 
-    fn default_interval() -> u64 {
-        30
+    fn default_interval() -> Duration {
+        Duration::from_secs(30)
     }
 }
 
@@ -661,6 +912,9 @@ impl Default for AudioConfig {
             scroll_up: Self::default_on_scroll_up(),
             scroll_down: Self::default_on_scroll_down(),
             scroll_step: Self::default_scroll_step(),
+            max_volume: Self::default_max_volume(),
+            device_popover: false,
+            mixer_popover: false,
         }
     }
 }
@@ -678,8 +932,8 @@ impl AudioConfig {
         vec!["".to_string(), "".to_string(), "".to_string()]
     }
 
-    fn default_interval() -> u64 {
-        250
+    fn default_interval() -> Duration {
+        Duration::from_millis(250)
     }
 
     fn default_on_scroll_up() -> String {
@@ -693,6 +947,40 @@ impl AudioConfig {
     fn default_scroll_step() -> i32 {
         5
     }
+
+    fn default_max_volume() -> i32 {
+        100
+    }
+}
+
+impl Default for MicrophoneConfig {
+    fn default() -> Self {
+        Self {
+            format: AudioConfig::default_format(),
+            icons: Self::default_icons(),
+            muted_icon: Self::default_muted_icon(),
+            interval: AudioConfig::default_interval(),
+            tooltip: default_tooltip(),
+            on_click: default_on_click(),
+            on_click_right: default_command(),
+            on_click_middle: default_command(),
+            scroll_up: AudioConfig::default_on_scroll_up(),
+            scroll_down: AudioConfig::default_on_scroll_down(),
+            scroll_step: AudioConfig::default_scroll_step(),
+            max_volume: AudioConfig::default_max_volume(),
+            device_popover: false,
+        }
+    }
+}
+
+impl MicrophoneConfig {
+    pub fn default_muted_icon() -> String {
+        "".to_string()
+    }
+
+    pub fn default_icons() -> Vec<String> {
+        vec!["".to_string()]
+    }
 }
 
 impl Default for ActiveWindowConfig {
@@ -719,6 +1007,7 @@ impl Default for ClockConfig {
             on_click: Self::default_on_click(),
             on_click_right: Self::default_on_click(),
             on_click_middle: Self::default_on_click(),
+            timezone: None,
         }
     }
 }
@@ -728,8 +1017,8 @@ impl ClockConfig {
         "%H:%M".to_string()
     }
 
-    fn default_interval() -> u64 {
-        300
+    fn default_interval() -> Duration {
+        Duration::from_millis(300)
     }
 
     fn default_tooltip_format() -> String {
@@ -746,23 +1035,9 @@ impl Config {
         let config_path = c_path;
 
         if config_path.exists() {
-            match fs::read_to_string(&config_path) {
-                Ok(content) => match toml::from_str::<Config>(&content) {
-                    Ok(mut config) => {
-                        println!("Loaded config from: {:?}", config_path);
-
-                        // MPRIS normalize
-                        config.mpris.normalize();
-
-                        return config;
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to parse config: {}. Using defaults.", e);
-                    }
-                },
-                Err(e) => {
-                    eprintln!("Failed to read config: {}. Using defaults.", e);
-                }
+            match Self::try_load(&config_path) {
+                Ok(config) => return config,
+                Err(e) => eprintln!("{}", e),
             }
         } else {
             println!("Config file not found at: {:?}", config_path);
@@ -777,6 +1052,53 @@ impl Config {
         std::process::exit(1);
     }
 
+    /// Read and parse the config without exiting on errors, used on reload.
+    pub fn try_load(config_path: &Path) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(config_path).map_err(|e| format!("Failed to read config: {}", e))?;
+        let mut config = toml::from_str::<Config>(&content)
+            .map_err(|e| format!("Failed to parse config: {}", e))?;
+
+        println!("Loaded config from: {:?}", config_path);
+
+        // MPRIS normalize
+        config.mpris.normalize();
+
+        config.instances = Self::parse_instances(&content);
+
+        Ok(config)
+    }
+
+    /// Collect `["module#instance"]` tables, which serde skips as unknown keys.
+    fn parse_instances(content: &str) -> HashMap<String, toml::Table> {
+        let Ok(table) = toml::from_str::<toml::Table>(content) else {
+            return HashMap::new();
+        };
+
+        table
+            .into_iter()
+            .filter_map(|(name, value)| match value {
+                toml::Value::Table(instance) if name.contains('#') => Some((name, instance)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Config of a module instance: the `["name#instance"]` table if there is
+    /// one, otherwise `base`, the shared table of the module.
+    pub fn instance_config<T: DeserializeOwned + Clone>(&self, name: &str, base: &T) -> T {
+        match self.instances.get(name) {
+            Some(table) => match table.clone().try_into::<T>() {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("Failed to parse config for {}: {}", name, e);
+                    base.clone()
+                }
+            },
+            None => base.clone(),
+        }
+    }
+
     pub fn get_config_path() -> PathBuf {
         let mut path = PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| String::from("~")));
         path.push(".config/riftbar/config.toml");
@@ -797,11 +1119,18 @@ modules_center = ["hyprland/workspaces"]
 modules_right = ["box/right"]
 position = "top"  # top, bottom, left, right
 layer = "top"     # background, bottom, top, overlay
+# output = "*"    # connector name like "DP-1", a list of them, or "*" for all outputs
+height = 30       # height of top/bottom bars, use width for left/right bars
+# floating = true # center the bar on its edge instead of spanning the output
+# length = 800    # size of a floating bar along its edge
+# mode = "autohide"  # normal, autohide: reveal the bar when the pointer touches the edge
+# hide_delay = "500ms"  # before an autohide bar slides out again, bare numbers are milliseconds
+# on_fullscreen = "hide"  # none, hide, lower: behaviour while a window is fullscreen (Hyprland)
 
 # Clock module configuration
 [clock]
 format = " %H:%M"
-interval = 300  # milliseconds
+interval = "300ms"  # durations like "500ms", "2s", "5m"; bare numbers keep their old unit
 tooltip = true
 tooltip_format = "%d %B %Y"
 on_click = "ewwii open calendar --toggle --no-daemonize & ewwii close on_clickcenter musiccenter"  # Optional: command to run on click
@@ -817,6 +1146,11 @@ on_click = "ewwii open calendar --toggle --no-daemonize & ewwii close on_clickce
 # %d _ Day of month (01_31)
 # %Y _ Year with century
 
+# Named instances use their own table and get the instance name as css class
+# ["clock#utc"]
+# format = "UTC %H:%M"
+# timezone = "UTC"
+
 [workspaces]
 # format = "{id} {icon}"
 # icons = { "active" = "A", "normal" = "N"}
@@ -829,26 +1163,34 @@ on_click = "bash ~/Dotfiles/scripts/quickcenter.sh &  ewwii close calendar"
 format = "{icon}"
 active_icons = ["󰤯","󰤟","󰤢","󰤥","󰤨"] 
 format_ethernet = " "
-interval = 5
+interval = "5s"
 tooltip = true
 
 # Audio module configuration
 [audio]
 format = "{icon}"
-interval = 150  # milliseconds
+interval = "150ms"  # only polled when the sound server can't be reached natively
 tooltip = true
 on_click = "bash ~/Dotfiles/scripts/quickcenter.sh &  ewwii close calendar"
 on_click_right = "nohup foot --override=colors.alpha=1 --app-id=binarydotsTUI -e wiremix >/dev/null 2>&1 &"
 on_scroll_up = ""
 on_scroll_down = ""
 scroll_step = 5
+max_volume = 100  # above 100 adds the "overamplified" class
+# device_popover = true  # right click picks the default output and input
+# mixer_popover = true  # middle click shows the volume of every application
+
+# Microphone (default audio source) configuration
+[microphone]
+format = "{icon}"
+tooltip = true
 
 # MPRIS (Media Player) configuration
 [mpris]
 format = "{icon}  {title} - {artist}"
 format_nothing = "No Media"
 length_lim = 32
-interval = 100  # milliseconds
+interval = "100ms"
 tooltip = true
 tooltip_format = "{artist}\n{album}\n{title}"
 
@@ -858,7 +1200,7 @@ format = "{icon}"
 icons = ["󰂎", "󰁺", "󰁻", "󰁼", "󰁽", "󰁾", "󰁿", "󰂁", "󰂂", "󰁹"]
 charging_icon = "󰂄"
 on_click = "bash ~/Dotfiles/scripts/quickcenter.sh &  ewwii close calendar"
-interval = 30
+interval = "30s"
 tooltip = true
 
 [tray]
@@ -909,8 +1251,16 @@ format = " "
 # on_click = "bash ~/Dotfiles/config/mako/scripts/walker.sh"
 # on_click_right = "bash ~/Dotfiles/config/mako/scripts/riftbar.sh -d"
 # exec = "~/Dotfiles/config/mako/scripts/riftbar.sh"
-# interval = 1
+# interval = "1s"
+# schedule = "1m"         # run at the start of every minute instead of every interval
+# exec_if = "pgrep mako"  # hide the module and skip exec while this command fails
+# hide_empty = true       # hide the module while exec prints nothing
+# signal = 8  # refresh right away with `pkill -RTMIN+8 riftbar`
 # format = "{}"
+# return_type = "json"  # output {"text", "alt", "tooltip", "class", "percentage"}
+# format_icons = ["󰕿", "󰖀", "󰕾"]  # {icon} by percentage, or a table keyed by alt
+# tooltip_exec = "~/Dotfiles/config/mako/scripts/riftbar.sh --list"  # run when the tooltip shows
+# tooltip_format = "{}"  # {} is the output of tooltip_exec, or the label without one
 
 [custom_modules.seperator]
 format = "|"
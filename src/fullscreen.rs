@@ -0,0 +1,94 @@
+// ============ fullscreen.rs ============
+use hyprland::data::{Monitors, Workspaces};
+use hyprland::shared::HyprData;
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    os::unix::net::UnixStream,
+    sync::mpsc,
+};
+
+/// Whether a fullscreen window is shown, keyed by monitor connector name.
+pub type FullscreenState = HashMap<String, bool>;
+
+/// Connect to Hyprland's socket2 and send the fullscreen state of every
+/// monitor down `tx` whenever an event may have changed it.
+pub fn start_listener(tx: mpsc::Sender<FullscreenState>) {
+    std::thread::spawn(move || {
+        let instance = match std::env::var("HYPRLAND_INSTANCE_SIGNATURE") {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        let runtime_dir =
+            std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/run/user/1000".to_string());
+        let socket_path = format!("{}/hypr/{}/.socket2.sock", runtime_dir, instance);
+
+        let stream = match UnixStream::connect(&socket_path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("[fullscreen] failed to connect to socket2: {e}");
+                return;
+            }
+        };
+
+        // A window may already be fullscreen when the bar starts
+        if let Some(state) = query_state()
+            && tx.send(state).is_err()
+        {
+            return;
+        }
+
+        let reader = BufReader::new(stream);
+
+        for line in reader.lines() {
+            let line = match line {
+                Ok(l) => l,
+                Err(e) => {
+                    eprintln!("[fullscreen] socket2 read error: {e}");
+                    break;
+                }
+            };
+
+            // fullscreen>> only reports the focused window, so anything that
+            // changes what a monitor shows triggers a full query
+            let event = line.split(">>").next().unwrap_or_default();
+            if !matches!(
+                event,
+                "fullscreen"
+                    | "workspace"
+                    | "focusedmon"
+                    | "closewindow"
+                    | "movewindow"
+                    | "activespecial"
+                    | "moveworkspace"
+            ) {
+                continue;
+            }
+
+            if let Some(state) = query_state()
+                && tx.send(state).is_err()
+            {
+                return;
+            }
+        }
+    });
+}
+
+fn query_state() -> Option<FullscreenState> {
+    let monitors = Monitors::get().ok()?;
+    let workspaces = Workspaces::get().ok()?;
+
+    let state = monitors
+        .iter()
+        .map(|monitor| {
+            let fullscreen = workspaces.iter().any(|workspace| {
+                workspace.fullscreen
+                    && (workspace.id == monitor.active_workspace.id
+                        || workspace.id == monitor.special_workspace.id)
+            });
+            (monitor.name.clone(), fullscreen)
+        })
+        .collect();
+
+    Some(state)
+}
@@ -0,0 +1,434 @@
+// ============ ipc.rs ============
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    cell::RefCell,
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+    },
+    time::Duration,
+};
+
+/// Version of the JSON protocol, bumped on incompatible changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How long a client waits for the main thread to answer.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One JSON request line: `{"version": 1, "id": 1, "command": "toggle", "args": ["main"]}`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Request {
+    #[serde(default = "default_version")]
+    pub version: u32,
+
+    /// Opaque value echoed back in the response.
+    #[serde(default)]
+    pub id: Value,
+
+    pub command: String,
+
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Reply to a request, `ok` with an optional `result` or an `error` message.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Response {
+    pub version: u32,
+
+    #[serde(default)]
+    pub id: Value,
+
+    pub ok: bool,
+
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub result: Value,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn default_version() -> u32 {
+    PROTOCOL_VERSION
+}
+
+impl Response {
+    fn new(id: Value, result: Result<Value, String>) -> Self {
+        match result {
+            Ok(result) => Self {
+                version: PROTOCOL_VERSION,
+                id,
+                ok: true,
+                result,
+                error: None,
+            },
+            Err(error) => Self {
+                version: PROTOCOL_VERSION,
+                id,
+                ok: false,
+                result: Value::Null,
+                error: Some(error),
+            },
+        }
+    }
+
+    /// Reply line for plain-text clients: `ok`, `ok <result>` or `error: <message>`.
+    fn to_text(&self) -> String {
+        match (&self.error, &self.result) {
+            (Some(error), _) => format!("error: {}", error),
+            (None, Value::Null) => "ok".to_string(),
+            (None, Value::String(result)) => format!("ok {}", result),
+            (None, result) => format!("ok {}", result),
+        }
+    }
+}
+
+/// Client streaming events after a `subscribe` request.
+struct Subscriber {
+    id: u64,
+    /// Event names the client asked for, empty for all of them.
+    events: Vec<String>,
+    tx: mpsc::Sender<String>,
+}
+
+static SUBSCRIBERS: Lazy<Mutex<Vec<Subscriber>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static NEXT_SUBSCRIBER: AtomicU64 = AtomicU64::new(0);
+
+/// Runs a request on the GTK main thread and replies to it.
+type Handler = Box<dyn Fn(Call)>;
+
+thread_local! {
+    /// Set by `start_listener` on the main thread.
+    static HANDLER: RefCell<Option<Handler>> = RefCell::new(None);
+}
+
+/// Send an event line `{"version": 1, "event": …, "data": …}` to every
+/// client subscribed to `event`. Emitted events:
+///
+/// - `bar`: a bar was opened or closed, `{"name", "monitor", "open"}`
+/// - `reload`: the config was reloaded
+/// - `revealer`: a revealer opened or closed, `{"name", "revealed"}`
+/// - `click`: a module was clicked, `{"module", "button"}`
+/// - `battery`: capacity or status changed, `{"capacity", "status"}`
+pub fn emit(event: &str, data: Value) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    if subscribers.is_empty() {
+        return;
+    }
+
+    let line = json!({
+        "version": PROTOCOL_VERSION,
+        "event": event,
+        "data": data,
+    })
+    .to_string();
+
+    // Drop clients that went away
+    subscribers.retain(|subscriber| {
+        let wanted = subscriber.events.is_empty() || subscriber.events.iter().any(|e| e == event);
+        !wanted || subscriber.tx.send(line.clone()).is_ok()
+    });
+}
+
+/// Whether a request arrived as JSON or as a plain `<command> <args…>` line.
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    Text,
+}
+
+impl Request {
+    fn parse(line: &str) -> Result<(Self, Format), String> {
+        let line = line.trim();
+
+        if line.starts_with('{') {
+            let request: Request =
+                serde_json::from_str(line).map_err(|e| format!("invalid request: {}", e))?;
+            if request.version != PROTOCOL_VERSION {
+                return Err(format!(
+                    "unsupported protocol version {}, expected {}",
+                    request.version, PROTOCOL_VERSION
+                ));
+            }
+            return Ok((request, Format::Json));
+        }
+
+        let mut parts = line.split_whitespace().map(str::to_string);
+        let command = parts.next().ok_or("empty request")?;
+        Ok((
+            Request {
+                version: PROTOCOL_VERSION,
+                id: Value::Null,
+                command,
+                args: parts.collect(),
+            },
+            Format::Text,
+        ))
+    }
+}
+
+/// A request handed to the GTK main thread, answered through `reply`.
+pub struct Call {
+    pub request: Request,
+    reply: mpsc::Sender<Response>,
+}
+
+impl Call {
+    pub fn reply(self, result: Result<Value, String>) {
+        let _ = self
+            .reply
+            .send(Response::new(self.request.id.clone(), result));
+    }
+}
+
+/// Socket of the instance named `instance`:
+/// `$XDG_RUNTIME_DIR/riftbar/<instance>.sock`, or a per-user directory in
+/// `/tmp` when no runtime directory is set.
+pub fn socket_path(instance: &str) -> PathBuf {
+    let dir = match std::env::var("XDG_RUNTIME_DIR") {
+        Ok(runtime_dir) => PathBuf::from(runtime_dir).join("riftbar"),
+        Err(_) => PathBuf::from(format!("/tmp/riftbar-{}", unsafe { libc::getuid() })),
+    };
+    dir.join(format!("{}.sock", instance))
+}
+
+/// Refuse a socket directory another user could have created or can write
+/// to, which matters for the shared `/tmp` fallback.
+fn check_socket_dir(dir: &Path) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(dir)
+        .map_err(|e| format!("failed to inspect {}: {}", dir.display(), e))?;
+    let uid = unsafe { libc::getuid() };
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o777 != 0o700 {
+        return Err(format!(
+            "{} must be a directory owned by uid {} with mode 0700",
+            dir.display(),
+            uid
+        ));
+    }
+    Ok(())
+}
+
+/// Spawns a background thread that listens on the Unix socket and hands
+/// every request to `handler` on the GTK main thread, writing back the reply.
+/// Only plain data crosses the thread boundary — no GTK types involved.
+/// Must be called on the main thread.
+///
+/// A socket left behind by a crashed instance is replaced, one that still
+/// answers belongs to a running instance and is an error.
+pub fn start_listener(socket: &Path, handler: impl Fn(Call) + 'static) -> Result<(), String> {
+    if let Some(dir) = socket.parent() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        check_socket_dir(dir)?;
+    }
+
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(format!(
+                "another riftbar instance is listening on {}",
+                socket.display()
+            ));
+        }
+        let _ = fs::remove_file(socket);
+    }
+
+    let listener = UnixListener::bind(socket)
+        .map_err(|e| format!("failed to bind {}: {}", socket.display(), e))?;
+    HANDLER.with(|current| *current.borrow_mut() = Some(Box::new(handler)));
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("IPC accept error: {}", e);
+                    continue;
+                }
+            };
+
+            // A slow client must not hold up the others
+            std::thread::spawn(move || handle_client(stream));
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_client(stream: UnixStream) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("IPC stream error: {}", e);
+            return;
+        }
+    };
+
+    let reader = BufReader::new(stream);
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("IPC read error: {}", e);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (response, format) = match Request::parse(&line) {
+            Ok((request, format)) if request.command == "subscribe" => {
+                let response = Response::new(request.id, Ok(Value::Null));
+                let reply = match format {
+                    Format::Json => serde_json::to_string(&response).unwrap_or_default(),
+                    Format::Text => response.to_text(),
+                };
+                if writeln!(writer, "{}", reply).is_ok() {
+                    stream_events(writer, request.args);
+                }
+                return;
+            }
+            Ok((request, format)) => {
+                println!("IPC: command='{}' args={:?}", request.command, request.args);
+                (dispatch(request), format)
+            }
+            Err(e) => {
+                eprintln!("Malformed IPC message: {:?}", line);
+                let format = if line.trim_start().starts_with('{') {
+                    Format::Json
+                } else {
+                    Format::Text
+                };
+                (Response::new(Value::Null, Err(e)), format)
+            }
+        };
+
+        let reply = match format {
+            Format::Json => serde_json::to_string(&response).unwrap_or_default(),
+            Format::Text => response.to_text(),
+        };
+
+        // Clients of the old protocol close without reading the reply
+        if writeln!(writer, "{}", reply).is_err() {
+            break;
+        }
+    }
+}
+
+/// Keep the connection open and write every subscribed event to it.
+fn stream_events(mut writer: UnixStream, events: Vec<String>) {
+    let id = NEXT_SUBSCRIBER.fetch_add(1, Ordering::Relaxed);
+    let (tx, rx) = mpsc::channel();
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .push(Subscriber { id, events, tx });
+
+    // Notice the client leaving without waiting for the next event: dropping
+    // the subscriber drops its sender, which ends the loop below
+    if let Ok(mut reader) = writer.try_clone() {
+        std::thread::spawn(move || {
+            let _ = std::io::copy(&mut reader, &mut std::io::sink());
+            SUBSCRIBERS
+                .lock()
+                .unwrap()
+                .retain(|subscriber| subscriber.id != id);
+        });
+    }
+
+    for line in rx {
+        if writeln!(writer, "{}", line).is_err() {
+            break;
+        }
+    }
+}
+
+/// Run the request on the main thread, waking its loop right away.
+fn dispatch(request: Request) -> Response {
+    let id = request.id.clone();
+    let (reply_tx, reply_rx) = mpsc::channel();
+    let call = Call {
+        request,
+        reply: reply_tx,
+    };
+
+    glib::idle_add_once(move || {
+        HANDLER.with(|handler| match handler.borrow().as_ref() {
+            Some(handler) => handler(call),
+            None => call.reply(Err("riftbar is shutting down".to_string())),
+        })
+    });
+
+    reply_rx
+        .recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| Response::new(id, Err("no reply from riftbar".to_string())))
+}
+
+/// Send one request to the running instance and wait for its response.
+pub fn send(socket: &Path, command: &str, args: &[String]) -> Result<Response, String> {
+    request(socket, command, args).map(|(response, _)| response)
+}
+
+/// Subscribe to `events` (all of them if empty) and pass every event line
+/// to `on_event` until the running instance closes the connection.
+pub fn subscribe(
+    socket: &Path,
+    events: &[String],
+    mut on_event: impl FnMut(&str),
+) -> Result<(), String> {
+    let (response, reader) = request(socket, "subscribe", events)?;
+    if !response.ok {
+        return Err(response.error.unwrap_or_default());
+    }
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| format!("failed to read event: {}", e))?;
+        on_event(&line);
+    }
+    Ok(())
+}
+
+/// Write a request and read the response line, returning the reader for
+/// whatever the instance sends afterwards.
+fn request(
+    socket: &Path,
+    command: &str,
+    args: &[String],
+) -> Result<(Response, BufReader<UnixStream>), String> {
+    if let Some(dir) = socket.parent() {
+        check_socket_dir(dir)?;
+    }
+    let mut stream = UnixStream::connect(socket)
+        .map_err(|e| format!("failed to connect to {}: {}", socket.display(), e))?;
+
+    let request = Request {
+        version: PROTOCOL_VERSION,
+        id: Value::from(std::process::id()),
+        command: command.to_string(),
+        args: args.to_vec(),
+    };
+    let line = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", line).map_err(|e| format!("failed to send request: {}", e))?;
+
+    let mut reader = BufReader::new(stream);
+    let mut reply = String::new();
+    reader
+        .read_line(&mut reply)
+        .map_err(|e| format!("failed to read response: {}", e))?;
+
+    let response = serde_json::from_str(&reply).map_err(|e| format!("invalid response: {}", e))?;
+    Ok((response, reader))
+}
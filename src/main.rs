@@ -1,30 +1,39 @@
 // ============ main.rs ============
 use gtk4 as gtk;
+use gtk4::gio;
 use gtk4::prelude::*;
 use gtk4_layer_shell::LayerShell;
 use std::{
-    cell::RefCell,
-    env, fs,
-    io::{BufRead, BufReader, Write},
-    os::unix::net::{UnixListener, UnixStream},
-    path::PathBuf,
+    cell::{Cell, RefCell},
+    env,
+    path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
     sync::mpsc,
 };
 
+mod autohide;
 mod config;
+mod fullscreen;
+mod ipc;
 mod modules;
 mod shared;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+thread_local! {
+    // Provider holding the current stylesheet, reloaded in place
+    static CSS_PROVIDER: RefCell<Option<gtk::CssProvider>> = const { RefCell::new(None) };
+}
+
 fn main() {
     let mut config_path = config::Config::get_config_path();
 
     let args: Vec<String> = env::args().collect();
 
     let mut use_gpu = false;
+    let mut check = false;
+    let mut instance = None;
+    let mut ipc_args = None;
 
     let mut i = 1;
     while i < args.len() {
@@ -41,19 +50,30 @@ fn main() {
             std::process::exit(1);
         } else if *arg == "--use-gpu" {
             use_gpu = true;
+            i += 1;
+        } else if matches!(*arg, "--check" | "check") {
+            check = true;
+            i += 1;
+        } else if *arg == "--instance" {
+            if i + 1 < args.len() {
+                // The name becomes a file name in the socket directory
+                let name = &args[i + 1];
+                if name.is_empty() || name.contains('/') || name.contains("..") {
+                    eprintln!("Invalid instance name: {:?}", name);
+                    std::process::exit(1);
+                }
+                instance = Some(name.clone());
+                i += 2;
+            } else {
+                std::process::exit(1);
+            }
         } else if matches!(*arg, "--ipc" | "-i") {
             if i + 1 < args.len() {
-                println!("[IPC]: Triggered ipc command: {}", args[i + 1]);
-                let write_target = if args.get(i + 2).is_some() {
-                    &args[i + 2]
-                } else {
-                    ""
-                };
-                let _ = ipc_writer(&args[i + 1], write_target);
-                std::process::exit(0)
+                ipc_args = Some(args[i + 1..].to_vec());
+                break;
             } else {
                 println!(
-                    "[IPC]: Error, IPC needs at least two arguments\n    toggle <bar.name> \n      open <bar.name> \n     close <bar.name>"
+                    "[IPC]: Error, IPC needs at least two arguments\n    toggle <bar.name> \n      open <bar.name> \n     close <bar.name> \n  autohide <bar.name> \n    reveal <revealer/name> [bar.name] \n  unreveal <revealer/name> [bar.name] \n toggle-reveal <revealer/name> [bar.name] \n    update <custom/name> <text|json> \n   refresh <custom/name> \n    reload"
                 );
                 std::process::exit(1)
             }
@@ -65,7 +85,22 @@ fn main() {
         }
     }
 
-    let config = config::Config::load(config_path);
+    let instance = instance_name(instance, &config_path);
+    let socket = ipc::socket_path(&instance);
+
+    if let Some(ipc_args) = ipc_args {
+        std::process::exit(ipc_command(&socket, &ipc_args[0], &ipc_args[1..]));
+    }
+
+    if check {
+        std::process::exit(config::check(&config_path));
+    }
+
+    // Before GTK, tokio or anything else starts a thread
+    shared::signals::block_signals();
+    shared::signals::start_listener();
+
+    let config = config::Config::load(config_path.clone());
 
     if !use_gpu || !config.general.use_gpu {
         unsafe {
@@ -73,423 +108,661 @@ fn main() {
         }
     }
 
-    let app = gtk::Application::new(Some("com.binaryharb.RiftBar"), Default::default());
+    let app = gtk::Application::new(Some(&application_id(&instance)), Default::default());
 
     // Stays on the GTK main thread only — Rc<RefCell> is fine here
-    let window_map: Rc<RefCell<Vec<(String, gtk::Window, bool)>>> =
-        Rc::new(RefCell::new(Vec::new()));
+    let config = Rc::new(RefCell::new(config));
+    let config_monitor: Rc<RefCell<Option<gio::FileMonitor>>> = Rc::new(RefCell::new(None));
+    let style_monitors: Rc<RefCell<Vec<gio::FileMonitor>>> = Rc::new(RefCell::new(Vec::new()));
+    let window_map: Rc<RefCell<Vec<Bar>>> = Rc::new(RefCell::new(Vec::new()));
+
+    // Let modules stop their timers and threads before the app goes away
+    app.connect_shutdown({
+        let window_map = Rc::clone(&window_map);
+        move |_| {
+            for bar in window_map.borrow().iter() {
+                bar.modules.iter().for_each(|module| module.teardown());
+            }
+        }
+    });
 
     app.connect_activate({
         let window_map = Rc::clone(&window_map);
+        let config = Rc::clone(&config);
         move |app| {
-            for (name, bar_config) in &config.bars {
-                let window = gtk::Window::new();
+            build_bars(app, &config.borrow(), &window_map);
 
-                // Initialize layer shell
-                window.init_layer_shell();
+            if let Some(display) = gtk::gdk::Display::default() {
+                let app = app.clone();
+                let config = Rc::clone(&config);
+                let window_map = Rc::clone(&window_map);
+                display.monitors().connect_items_changed(move |_, _, _, _| {
+                    sync_monitors(&app, &config.borrow(), &window_map);
+                });
+            }
 
-                window.set_namespace(Some(&bar_config.namespace));
-                if bar_config.reserve_space {
-                    window.auto_exclusive_zone_enable();
-                } else {
-                    LayerShell::set_exclusive_zone(&window, 0);
+            // Load CSS after window is set up
+            apply_css_to_gtk();
+            watch_style(&style_monitors);
+
+            for bar in window_map.borrow().iter() {
+                if bar.open_on_launch {
+                    bar.show();
                 }
-                window.set_application(Some(app));
-                window.add_css_class(name);
-                window.add_css_class("bar-container");
-
-                // Set layer from config
-                let layer = match bar_config.layer.as_str() {
-                    "background" => gtk4_layer_shell::Layer::Background,
-                    "bottom" => gtk4_layer_shell::Layer::Bottom,
-                    "overlay" => gtk4_layer_shell::Layer::Overlay,
-                    _ => gtk4_layer_shell::Layer::Top,
-                };
-                window.set_layer(layer);
-
-                // Set anchors based on position
-                match bar_config.position.as_str() {
-                    "bottom" => {
-                        window.set_anchor(gtk4_layer_shell::Edge::Bottom, true);
-                        window.set_anchor(gtk4_layer_shell::Edge::Left, true);
-                        window.set_anchor(gtk4_layer_shell::Edge::Right, true);
-                    }
-                    "left" => {
-                        window.set_anchor(gtk4_layer_shell::Edge::Top, true);
-                        window.set_anchor(gtk4_layer_shell::Edge::Bottom, true);
-                        window.set_anchor(gtk4_layer_shell::Edge::Left, true);
+            }
+
+            if std::env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok() {
+                let (tx, rx) = mpsc::channel::<fullscreen::FullscreenState>();
+                fullscreen::start_listener(tx);
+
+                let window_map = Rc::clone(&window_map);
+                gtk::glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+                    while let Ok(state) = rx.try_recv() {
+                        for bar in window_map.borrow().iter() {
+                            if let Some(connector) = bar.connector() {
+                                bar.set_fullscreen(state.get(connector.as_str()) == Some(&true));
+                            }
+                        }
                     }
-                    "right" => {
-                        window.set_anchor(gtk4_layer_shell::Edge::Top, true);
-                        window.set_anchor(gtk4_layer_shell::Edge::Bottom, true);
-                        window.set_anchor(gtk4_layer_shell::Edge::Right, true);
+                    gtk::glib::ControlFlow::Continue
+                });
+            }
+
+            if config.borrow().general.watch_config {
+                let app = app.clone();
+                let path = config_path.clone();
+                let config = Rc::clone(&config);
+                let window_map = Rc::clone(&window_map);
+                *config_monitor.borrow_mut() = watch_file(&config_path, move || {
+                    if let Err(e) = reload_config(&app, &path, &config, &window_map) {
+                        eprintln!("{}", e);
                     }
-                    _ => {
-                        // top
-                        window.set_anchor(gtk4_layer_shell::Edge::Top, true);
-                        window.set_anchor(gtk4_layer_shell::Edge::Left, true);
-                        window.set_anchor(gtk4_layer_shell::Edge::Right, true);
+                });
+            }
+
+            if config.borrow().general.enable_ipc {
+                println!("Starting IPC on {}...", socket.display());
+                let handler = {
+                    let app = app.clone();
+                    let config_path = config_path.clone();
+                    let config = Rc::clone(&config);
+                    let window_map = Rc::clone(&window_map);
+                    move |call: ipc::Call| {
+                        let result =
+                            handle_ipc(&app, &config_path, &config, &window_map, &call.request);
+                        if let Err(e) = &result {
+                            eprintln!("[IPC]: {}", e);
+                        }
+                        call.reply(result);
                     }
+                };
+                if let Err(e) = ipc::start_listener(&socket, handler) {
+                    eprintln!("[IPC]: {}, IPC disabled", e);
+                    return;
                 }
 
-                window.set_margin(gtk4_layer_shell::Edge::Top, bar_config.margin_top);
-                window.set_margin(gtk4_layer_shell::Edge::Bottom, bar_config.margin_bottom);
-                window.set_margin(gtk4_layer_shell::Edge::Left, bar_config.margin_left);
-                window.set_margin(gtk4_layer_shell::Edge::Right, bar_config.margin_right);
+                app.connect_shutdown({
+                    let socket = socket.clone();
+                    move |_| {
+                        let _ = std::fs::remove_file(&socket);
+                    }
+                });
+            }
+        }
+    });
 
-                let orientation = match bar_config.position.as_str() {
-                    "right" | "left" => gtk::Orientation::Vertical,
-                    _ => gtk::Orientation::Horizontal,
-                };
+    app.run_with_args::<String>(&[]);
+}
 
-                if bar_config.position.as_str() != "right" && bar_config.position.as_str() != "left"
-                {
-                    // Use a center box for proper three-column layout
-                    let layout_container = gtk::CenterBox::new();
-                    layout_container.add_css_class("riftbar");
-
-                    // Left section
-                    let left_box = gtk::Box::new(orientation, bar_config.spacing);
-                    left_box.set_halign(gtk::Align::Start);
-                    left_box.set_hexpand(true);
-                    left_box.set_vexpand(false);
-                    left_box.add_css_class("left-section");
-                    if bar_config.modules_left.is_some() {
-                        build_modules(
-                            &left_box,
-                            &bar_config.modules_left.clone().unwrap_or_default(),
-                            &config,
-                            0,
-                        );
-                    }
+/// Build the window of `bar_config` with all of its modules, still hidden.
+/// Without a `monitor` the compositor picks the output.
+fn build_bar(
+    app: &gtk::Application,
+    name: &str,
+    bar_config: &config::BarConfig,
+    config: &config::Config,
+    monitor: Option<&gtk::gdk::Monitor>,
+) -> Bar {
+    let window = gtk::Window::new();
 
-                    // Center section
-                    let center_box = gtk::Box::new(orientation, bar_config.spacing);
-                    center_box.set_halign(gtk::Align::Center);
-                    center_box.set_hexpand(true);
-                    center_box.set_vexpand(false);
-                    center_box.add_css_class("center-section");
-                    if bar_config.modules_center.is_some() {
-                        build_modules(
-                            &center_box,
-                            &bar_config.modules_center.clone().unwrap_or_default(),
-                            &config,
-                            0,
-                        );
-                    }
+    // Initialize layer shell
+    window.init_layer_shell();
 
-                    // Right section
-                    let right_box = gtk::Box::new(orientation, bar_config.spacing);
-                    right_box.set_halign(gtk::Align::End);
-                    right_box.set_hexpand(true);
-                    right_box.set_vexpand(false);
-                    right_box.add_css_class("right-section");
-                    if bar_config.modules_right.is_some() {
-                        build_modules(
-                            &right_box,
-                            &bar_config.modules_right.clone().unwrap_or_default(),
-                            &config,
-                            0,
-                        );
-                    }
+    if let Some(monitor) = monitor {
+        window.set_monitor(Some(monitor));
+        if let Some(connector) = monitor.connector() {
+            window.add_css_class(&connector);
+        }
+    }
 
-                    // Attach to center box - each section gets equal width
-                    layout_container.set_start_widget(Some(&left_box));
-                    layout_container.set_center_widget(Some(&center_box));
-                    layout_container.set_end_widget(Some(&right_box));
+    window.set_namespace(Some(&bar_config.namespace));
+    if bar_config.reserve_space {
+        window.auto_exclusive_zone_enable();
+    } else {
+        LayerShell::set_exclusive_zone(&window, 0);
+    }
+    window.set_application(Some(app));
+    window.add_css_class(name);
+    window.add_css_class("bar-container");
+
+    // Set layer from config
+    let layer = match bar_config.layer.as_str() {
+        "background" => gtk4_layer_shell::Layer::Background,
+        "bottom" => gtk4_layer_shell::Layer::Bottom,
+        "overlay" => gtk4_layer_shell::Layer::Overlay,
+        _ => gtk4_layer_shell::Layer::Top,
+    };
+    window.set_layer(layer);
+
+    // Set anchors based on position, floating bars are only anchored to
+    // their own edge so the compositor centers them along it
+    let stretch = !bar_config.floating;
+    match bar_config.position.as_str() {
+        "bottom" => {
+            window.set_anchor(gtk4_layer_shell::Edge::Bottom, true);
+            window.set_anchor(gtk4_layer_shell::Edge::Left, stretch);
+            window.set_anchor(gtk4_layer_shell::Edge::Right, stretch);
+        }
+        "left" => {
+            window.set_anchor(gtk4_layer_shell::Edge::Top, stretch);
+            window.set_anchor(gtk4_layer_shell::Edge::Bottom, stretch);
+            window.set_anchor(gtk4_layer_shell::Edge::Left, true);
+        }
+        "right" => {
+            window.set_anchor(gtk4_layer_shell::Edge::Top, stretch);
+            window.set_anchor(gtk4_layer_shell::Edge::Bottom, stretch);
+            window.set_anchor(gtk4_layer_shell::Edge::Right, true);
+        }
+        _ => {
+            // top
+            window.set_anchor(gtk4_layer_shell::Edge::Top, true);
+            window.set_anchor(gtk4_layer_shell::Edge::Left, stretch);
+            window.set_anchor(gtk4_layer_shell::Edge::Right, stretch);
+        }
+    }
 
-                    // Set css class
-                    layout_container.add_css_class(name);
+    // Size across the bar from height/width, along it from length.
+    // -1 leaves the size to the content
+    let length = bar_config.length.map_or(-1, |length| length as i32);
+    match bar_config.position.as_str() {
+        "left" | "right" => {
+            let width = bar_config.width.map_or(-1, |width| width as i32);
+            window.set_default_size(width, length);
+        }
+        _ => window.set_default_size(length, bar_config.height as i32),
+    }
 
-                    window.set_child(Some(&layout_container));
-                } else {
-                    let layout_container = gtk::Overlay::new();
-                    layout_container.add_css_class("riftbar");
-
-                    let main_vbox = gtk::Box::new(gtk::Orientation::Vertical, 0);
-
-                    let start_box = gtk::Box::new(orientation, bar_config.spacing);
-                    start_box.set_halign(gtk::Align::Fill);
-                    start_box.set_hexpand(true);
-                    start_box.add_css_class("left-section");
-                    build_modules(
-                        &start_box,
-                        &bar_config.modules_left.clone().unwrap_or_default(),
-                        &config,
-                        0,
-                    );
-
-                    main_vbox.append(&start_box);
-
-                    let spacer = gtk::Box::new(gtk::Orientation::Vertical, 0);
-                    spacer.set_vexpand(true);
-                    main_vbox.append(&spacer);
-
-                    let end_box = gtk::Box::new(orientation, bar_config.spacing);
-                    end_box.set_halign(gtk::Align::Fill);
-                    end_box.set_hexpand(true);
-                    end_box.add_css_class("right-section");
-                    build_modules(
-                        &end_box,
-                        &bar_config.modules_right.clone().unwrap_or_default(),
-                        &config,
-                        0,
-                    );
-
-                    main_vbox.append(&end_box);
-
-                    layout_container.set_child(Some(&main_vbox));
-
-                    let center_box = gtk::Box::new(orientation, bar_config.spacing);
-                    center_box.add_css_class("center-section");
-                    build_modules(
-                        &center_box,
-                        &bar_config.modules_center.clone().unwrap_or_default(),
-                        &config,
-                        0,
-                    );
-
-                    layout_container.add_overlay(&center_box);
-                    center_box.set_halign(gtk::Align::Center);
-                    center_box.set_valign(gtk::Align::Center);
-
-                    // Set css class
-                    layout_container.add_css_class(name);
-
-                    window.set_child(Some(&layout_container));
-                }
+    window.set_margin(gtk4_layer_shell::Edge::Top, bar_config.margin_top);
+    window.set_margin(gtk4_layer_shell::Edge::Bottom, bar_config.margin_bottom);
+    window.set_margin(gtk4_layer_shell::Edge::Left, bar_config.margin_left);
+    window.set_margin(gtk4_layer_shell::Edge::Right, bar_config.margin_right);
 
-                window_map
-                    .borrow_mut()
-                    .push((name.clone(), window, bar_config.open_on_launch));
-            }
+    let mut bar_modules = Vec::new();
 
-            // Load CSS after window is set up
-            apply_css_to_gtk();
+    let orientation = match bar_config.position.as_str() {
+        "right" | "left" => gtk::Orientation::Vertical,
+        _ => gtk::Orientation::Horizontal,
+    };
 
-            for (_, window, open_on_launch) in window_map.borrow().iter() {
-                if *open_on_launch {
-                    window.present();
-                }
-            }
+    if bar_config.position.as_str() != "right" && bar_config.position.as_str() != "left" {
+        // Use a center box for proper three-column layout
+        let layout_container = gtk::CenterBox::new();
+        layout_container.add_css_class("riftbar");
+
+        // Left section
+        let left_box = gtk::Box::new(orientation, bar_config.spacing);
+        left_box.set_halign(gtk::Align::Start);
+        left_box.set_hexpand(true);
+        left_box.set_vexpand(false);
+        left_box.add_css_class("left-section");
+        if bar_config.modules_left.is_some() {
+            bar_modules.extend(modules::build_modules(
+                &left_box,
+                &bar_config.modules_left.clone().unwrap_or_default(),
+                config,
+                0,
+            ));
+        }
 
-            if config.general.enable_ipc {
-                println!("Starting IPC...");
-                let (tx, rx) = mpsc::channel::<(String, String)>();
-                ipc_listener(tx);
+        // Center section
+        let center_box = gtk::Box::new(orientation, bar_config.spacing);
+        center_box.set_halign(gtk::Align::Center);
+        center_box.set_hexpand(true);
+        center_box.set_vexpand(false);
+        center_box.add_css_class("center-section");
+        if bar_config.modules_center.is_some() {
+            bar_modules.extend(modules::build_modules(
+                &center_box,
+                &bar_config.modules_center.clone().unwrap_or_default(),
+                config,
+                0,
+            ));
+        }
 
-                // Poll the channel on the GTK main thread every 50ms
-                let window_map = Rc::clone(&window_map);
-                gtk::glib::timeout_add_local(std::time::Duration::from_millis(500), move || {
-                    while let Ok((command, target)) = rx.try_recv() {
-                        let map = window_map.borrow();
+        // Right section
+        let right_box = gtk::Box::new(orientation, bar_config.spacing);
+        right_box.set_halign(gtk::Align::End);
+        right_box.set_hexpand(true);
+        right_box.set_vexpand(false);
+        right_box.add_css_class("right-section");
+        if bar_config.modules_right.is_some() {
+            bar_modules.extend(modules::build_modules(
+                &right_box,
+                &bar_config.modules_right.clone().unwrap_or_default(),
+                config,
+                0,
+            ));
+        }
 
-                        /* match command.as_str() {
-                            "reload-style" => {
-                                apply_css_to_gtk();
-                            }
-                        } */
+        // Attach to center box - each section gets equal width
+        layout_container.set_start_widget(Some(&left_box));
+        layout_container.set_center_widget(Some(&center_box));
+        layout_container.set_end_widget(Some(&right_box));
 
-                        if command.as_str() == "reload-style" {
-                            apply_css_to_gtk();
-                            continue;
-                        }
+        // Set css class
+        layout_container.add_css_class(name);
 
-                        let matched: Vec<&gtk::Window> = if target == "*" {
-                            map.iter().map(|(_, w, _)| w).collect()
-                        } else {
-                            map.iter()
-                                .filter(|(name, _, _)| name == &target)
-                                .map(|(_, w, _)| w)
-                                .collect()
-                        };
-
-                        if matched.is_empty() && command.as_str() != "reload-style" {
-                            eprintln!("[IPC]: no window named '{}'", target);
-                            continue;
-                        }
+        window.set_child(Some(&layout_container));
+    } else {
+        let layout_container = gtk::Overlay::new();
+        layout_container.add_css_class("riftbar");
+
+        let main_vbox = gtk::Box::new(gtk::Orientation::Vertical, 0);
+
+        let start_box = gtk::Box::new(orientation, bar_config.spacing);
+        start_box.set_halign(gtk::Align::Fill);
+        start_box.set_hexpand(true);
+        start_box.add_css_class("left-section");
+        bar_modules.extend(modules::build_modules(
+            &start_box,
+            &bar_config.modules_left.clone().unwrap_or_default(),
+            config,
+            0,
+        ));
+
+        main_vbox.append(&start_box);
+
+        let spacer = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        spacer.set_vexpand(true);
+        main_vbox.append(&spacer);
+
+        let end_box = gtk::Box::new(orientation, bar_config.spacing);
+        end_box.set_halign(gtk::Align::Fill);
+        end_box.set_hexpand(true);
+        end_box.add_css_class("right-section");
+        bar_modules.extend(modules::build_modules(
+            &end_box,
+            &bar_config.modules_right.clone().unwrap_or_default(),
+            config,
+            0,
+        ));
+
+        main_vbox.append(&end_box);
+
+        layout_container.set_child(Some(&main_vbox));
+
+        let center_box = gtk::Box::new(orientation, bar_config.spacing);
+        center_box.add_css_class("center-section");
+        bar_modules.extend(modules::build_modules(
+            &center_box,
+            &bar_config.modules_center.clone().unwrap_or_default(),
+            config,
+            0,
+        ));
+
+        layout_container.add_overlay(&center_box);
+        center_box.set_halign(gtk::Align::Center);
+        center_box.set_valign(gtk::Align::Center);
+
+        // Set css class
+        layout_container.add_css_class(name);
+
+        window.set_child(Some(&layout_container));
+    }
 
-                        for window in matched {
-                            match command.as_str() {
-                                "toggle" => {
-                                    if window.is_visible() {
-                                        window.set_visible(false);
-                                    } else {
-                                        window.present();
-                                    }
-                                }
-                                "open" => {
-                                    window.present();
-                                }
-                                "close" => {
-                                    window.set_visible(false);
-                                }
-                                other => {
-                                    eprintln!("[IPC]: unknown command '{}'", other);
-                                }
-                            }
-                        }
-                    }
-                    gtk::glib::ControlFlow::Continue
-                });
+    let autohide = (bar_config.mode == "autohide")
+        .then(|| autohide::AutoHide::new(app, &window, bar_config, monitor));
+
+    Bar {
+        name: name.to_string(),
+        window,
+        layer,
+        on_fullscreen: bar_config.on_fullscreen.clone(),
+        covered: Cell::new(false),
+        autohide,
+        open_on_launch: bar_config.open_on_launch,
+        monitor: monitor.cloned(),
+        modules: bar_modules,
+    }
+}
+
+/// Build the bars of `config` missing from `window_map`. Bars with an
+/// `output` get one window per matching monitor, others a single window.
+fn build_bars(app: &gtk::Application, config: &config::Config, window_map: &Rc<RefCell<Vec<Bar>>>) {
+    let monitors = monitors();
+    let exists = |name: &str, monitor: Option<&gtk::gdk::Monitor>| {
+        window_map
+            .borrow()
+            .iter()
+            .any(|bar| bar.name == name && bar.monitor.as_ref() == monitor)
+    };
+
+    for (name, bar_config) in &config.bars {
+        let Some(output) = &bar_config.output else {
+            if !exists(name, None) {
+                let bar = build_bar(app, name, bar_config, config, None);
+                window_map.borrow_mut().push(bar);
+            }
+            continue;
+        };
+
+        for monitor in &monitors {
+            let Some(connector) = monitor.connector() else {
+                continue;
+            };
+            if output.matches(&connector) && !exists(name, Some(monitor)) {
+                let bar = build_bar(app, name, bar_config, config, Some(monitor));
+                window_map.borrow_mut().push(bar);
             }
         }
-    });
+    }
+}
 
-    app.run_with_args::<String>(&[]);
+fn monitors() -> Vec<gtk::gdk::Monitor> {
+    let Some(display) = gtk::gdk::Display::default() else {
+        return Vec::new();
+    };
+
+    let list = display.monitors();
+    (0..list.n_items())
+        .filter_map(|i| list.item(i).and_downcast::<gtk::gdk::Monitor>())
+        .collect()
 }
 
-fn build_modules(
-    container: &gtk::Box,
-    module_names: &[String],
+/// Follow monitor hotplugs: bars of removed monitors are destroyed and new
+/// monitors get the bars whose `output` matches them.
+fn sync_monitors(
+    app: &gtk::Application,
     config: &config::Config,
-    container_type: i32,
+    window_map: &Rc<RefCell<Vec<Bar>>>,
 ) {
-    let container_name = match container_type {
-        0 => "",
-        1 => " in box",
-        2 => " in revealer",
-        _ => "",
-    };
+    let monitors = monitors();
+    window_map.borrow_mut().retain(|bar| {
+        let connected = bar
+            .monitor
+            .as_ref()
+            .is_none_or(|monitor| monitors.contains(monitor));
+        if !connected {
+            bar.destroy();
+        }
+        connected
+    });
 
-    println!("Building modules{}: {:?}", container_name, module_names);
+    let built = window_map.borrow().len();
+    build_bars(app, config, window_map);
 
-    let container_orientation = container.orientation();
+    for bar in &window_map.borrow()[built..] {
+        if bar.open_on_launch {
+            bar.show();
+        }
+    }
+}
+
+/// Re-read the config and rebuild every bar. A config that fails to load is
+/// reported and the running bars are left untouched.
+fn reload_config(
+    app: &gtk::Application,
+    config_path: &Path,
+    config: &Rc<RefCell<config::Config>>,
+    window_map: &Rc<RefCell<Vec<Bar>>>,
+) -> Result<(), String> {
+    let new_config = config::Config::try_load(config_path)
+        .map_err(|e| format!("{}. Keeping the current config.", e))?;
+
+    // Keep the app alive while no bar window exists
+    let _hold = app.hold();
+
+    for bar in window_map.borrow_mut().drain(..) {
+        bar.destroy();
+    }
+
+    *config.borrow_mut() = new_config;
+    build_bars(app, &config.borrow(), window_map);
+
+    for bar in window_map.borrow().iter() {
+        if bar.open_on_launch {
+            bar.show();
+        }
+    }
 
-    for name in module_names {
-        match name.as_str() {
-            "clock" => {
-                let clock_config = modules::ClockConfig::from_config(&config.clock);
-                let clock = modules::ClockWidget::new(clock_config);
-                container.append(clock.widget());
+    println!("Config reloaded");
+    ipc::emit("reload", serde_json::Value::Null);
+    Ok(())
+}
+
+/// Run an IPC request on the main thread and return its result.
+fn handle_ipc(
+    app: &gtk::Application,
+    config_path: &Path,
+    config: &Rc<RefCell<config::Config>>,
+    window_map: &Rc<RefCell<Vec<Bar>>>,
+    request: &ipc::Request,
+) -> Result<serde_json::Value, String> {
+    let command = request.command.as_str();
+    match command {
+        "reload" => {
+            reload_config(app, config_path, config, window_map)?;
+            return Ok(serde_json::Value::Null);
+        }
+        "reload-style" => {
+            apply_css_to_gtk();
+            return Ok(serde_json::Value::Null);
+        }
+        "list-bars" => {
+            let bars = window_map.borrow().iter().map(Bar::state).collect();
+            return Ok(serde_json::Value::Array(bars));
+        }
+        "list-modules" => {
+            let target = request
+                .args
+                .first()
+                .ok_or("'list-modules' needs a bar name")?;
+            let map = window_map.borrow();
+            let bar = map
+                .iter()
+                .find(|bar| bar.name == *target)
+                .ok_or_else(|| format!("no window named '{}'", target))?;
+            let names: Vec<String> = bar
+                .modules
+                .iter()
+                .flat_map(|module| module.names())
+                .collect();
+            return Ok(serde_json::json!(names));
+        }
+        "get" => {
+            let name = request.args.first().ok_or("'get' needs a module name")?;
+            let bar_name = request.args.get(1);
+            let map = window_map.borrow();
+            return map
+                .iter()
+                .filter(|bar| bar_name.is_none_or(|bar_name| bar.name == *bar_name))
+                .find_map(|bar| {
+                    let module = bar.modules.iter().find_map(|module| module.find(name))?;
+                    let mut state = module.state();
+                    state["name"] = serde_json::json!(module.name);
+                    state["bar"] = serde_json::json!(bar.name);
+                    Some(state)
+                })
+                .ok_or_else(|| format!("no module named '{}'", name));
+        }
+        "reveal" | "unreveal" | "toggle-reveal" => {
+            let name = request
+                .args
+                .first()
+                .ok_or_else(|| format!("'{}' needs a revealer name", command))?;
+            let bar_name = request.args.get(1);
+            let map = window_map.borrow();
+
+            let revealers: Vec<gtk::Revealer> = modules::revealers(name)
+                .into_iter()
+                .filter(|revealer| {
+                    bar_name.is_none_or(|bar_name| {
+                        map.iter()
+                            .any(|bar| bar.name == *bar_name && revealer.is_ancestor(&bar.window))
+                    })
+                })
+                .collect();
+
+            if revealers.is_empty() {
+                return Err(format!("no revealer named '{}'", name));
             }
-            "tray" => {
-                let tray_config = modules::TrayConfig {
-                    spacing: config.tray.spacing,
-                    icon_size: config.tray.icon_size,
+
+            for revealer in revealers {
+                let reveal = match command {
+                    "reveal" => true,
+                    "unreveal" => false,
+                    _ => !revealer.reveals_child(),
                 };
-                let tray = modules::TrayWidget::new(tray_config, container_orientation);
-                container.append(tray.widget());
-            }
-            "hyprland/workspaces" => {
-                let workspaces_config = Arc::new(modules::WorkspacesConfig::from_config(
-                    &config.workspaces,
-                    container_orientation,
-                ));
-                let workspaces = modules::HyprWorkspacesWidget::new(workspaces_config);
-                container.append(workspaces.widget());
-            }
-            "active_window" => {
-                let act_win_config =
-                    modules::ActiveWindowConfig::from_config(&config.active_window);
-                let act_win = modules::ActiveWindowWidget::new(act_win_config);
-                container.append(act_win.widget());
-            }
-            "mpris" => {
-                let mpris_config = modules::MprisConfig::from_config(&config.mpris);
-                let mpris = modules::MprisWidget::new(mpris_config);
-                container.append(mpris.widget());
-            }
-            "network" => {
-                let network_config = Arc::new(modules::NetworkConfig::from_config(&config.network));
-                let network = modules::NetworkWidget::new(network_config);
-                container.append(network.widget());
-            }
-            "battery" => {
-                let battery_config = Arc::new(modules::BatteryConfig::from_config(&config.battery));
-                let battery = modules::BatteryWidget::new(battery_config);
-                container.append(battery.widget());
+                revealer.set_reveal_child(reveal);
             }
-            "audio" => {
-                let audio_config = modules::AudioConfig::from_config(&config.audio);
-                let audio = modules::AudioWidget::new(audio_config);
-                container.append(audio.widget());
-            }
-            name if name.starts_with("custom/") => {
-                let custom_name = name.strip_prefix("custom/").unwrap();
-                if let Some(custom_config) = config.custom_modules.get(custom_name) {
-                    let custom = modules::CustomModuleWidget::new(modules::CustomModuleConfig {
-                        name: custom_name,
-                        on_click: custom_config.on_click.clone(),
-                        on_click_right: custom_config.on_click_right.clone(),
-                        on_click_middle: custom_config.on_click_middle.clone(),
-                        scroll_up: custom_config.scroll_up.clone(),
-                        scroll_down: custom_config.scroll_down.clone(),
-                        exec: custom_config.exec.clone(),
-                        listen: custom_config.listen,
-                        interval: custom_config.interval,
-                        format: custom_config.format.clone(),
-                    });
-                    container.append(custom.widget());
+            return Ok(serde_json::Value::Null);
+        }
+        "update" => {
+            let name = request
+                .args
+                .first()
+                .ok_or("'update' needs a custom module name")?;
+            // Plain-text requests arrive split on whitespace
+            let text = request.args[1..].join(" ");
+            modules::update_custom(name, &text)?;
+            return Ok(serde_json::Value::Null);
+        }
+        "refresh" => {
+            let name = request
+                .args
+                .first()
+                .ok_or("'refresh' needs a custom module name")?;
+            modules::refresh_custom(name)?;
+            return Ok(serde_json::Value::Null);
+        }
+        "toggle" | "open" | "close" | "autohide" => {}
+        other => return Err(format!("unknown command '{}'", other)),
+    }
+
+    let target = request
+        .args
+        .first()
+        .ok_or_else(|| format!("'{}' needs a bar name or '*'", command))?;
+
+    let map = window_map.borrow();
+    let matched: Vec<&Bar> = if target == "*" {
+        map.iter().collect()
+    } else {
+        map.iter().filter(|bar| bar.name == *target).collect()
+    };
+
+    if matched.is_empty() {
+        return Err(format!("no window named '{}'", target));
+    }
+
+    for bar in matched {
+        match command {
+            "toggle" => {
+                if bar.is_open() {
+                    bar.hide();
+                } else {
+                    bar.show();
                 }
             }
-            name if name.starts_with("sliders/") => {
-                let slider_name = name.strip_prefix("sliders/").unwrap();
-                if let Some(slider_config) = config.sliders.get(slider_name) {
-                    let custom = modules::SliderModuleWidget::new(modules::SliderModuleConfig {
-                        name: slider_name,
-                        exec: slider_config.exec.clone(),
-                        listen: slider_config.listen,
-                        length: slider_config.length,
-                        interval: slider_config.interval,
-                        scroll_cmd: slider_config.scroll_cmd.clone(),
-                        scroll_step: slider_config.scroll_step,
-                    });
-                    container.append(custom.widget());
-                }
+            "open" => {
+                bar.show();
             }
-            name if name.starts_with("box/") => {
-                let box_name = name.strip_prefix("box/").unwrap();
-                if let Some(box_config) = config.boxes.get(box_name) {
-                    let box_widget_config = modules::BoxWidgetConfig {
-                        modules: box_config.modules.clone(),
-                        gestures: crate::shared::Gestures {
-                            on_click: box_config.on_click.clone(),
-                            on_click_middle: box_config.on_click_middle.clone(),
-                            on_click_right: box_config.on_click_right.clone(),
-                            scroll_up: box_config.scroll_up.clone(),
-                            scroll_down: box_config.scroll_down.clone(),
-                        },
-                        spacing: box_config.spacing,
-                        orientation: box_config
-                            .orientation
-                            .clone()
-                            .unwrap_or_else(|| "horizontal".to_string()),
-                    };
-                    let box_widget = modules::BoxWidget::new(box_name, box_widget_config, config);
-                    container.append(box_widget.widget());
-                }
+            "close" => {
+                bar.hide();
             }
-            name if name.starts_with("revealer/") => {
-                let revealer_name = name.strip_prefix("revealer/").unwrap();
-                if let Some(revealer_config) = config.revealers.get(revealer_name) {
-                    let revealer_widget_config = modules::RevealerConfig {
-                        modules: revealer_config.modules.clone(),
-                        spacing: revealer_config.spacing,
-                        orientation: revealer_config
-                            .orientation
-                            .clone()
-                            .unwrap_or_else(|| "horizontal".to_string()),
-                        trigger: revealer_config.trigger.clone().unwrap_or_default(),
-                        transition: revealer_config
-                            .transition
-                            .clone()
-                            .unwrap_or_else(|| "slide_left".to_string()),
-                        transition_duration: revealer_config.transition_duration.unwrap_or(200),
-                        reveal_on_hover: revealer_config.reveal_on_hover.unwrap_or(false),
-                    };
-                    let revealer_widget =
-                        modules::RevealerWidget::new(revealer_name, revealer_widget_config, config);
-                    container.append(revealer_widget.widget());
-                }
+            _ => match &bar.autohide {
+                Some(autohide) => autohide.toggle(),
+                None => return Err(format!("bar '{}' has no autohide mode", bar.name)),
+            },
+        }
+    }
+
+    Ok(serde_json::Value::Null)
+}
+
+/// Send an IPC command to the running bar and print its reply. Returns the
+/// exit code: 0 on success, 1 if the command failed, 2 if riftbar could not
+/// be reached.
+fn ipc_command(socket: &Path, command: &str, args: &[String]) -> i32 {
+    if command == "subscribe" {
+        return match ipc::subscribe(socket, args, |event| println!("{}", event)) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("[IPC]: {}", e);
+                2
             }
-            _ => {
-                eprintln!("Unknown module: {}", name);
+        };
+    }
+
+    match ipc::send(socket, command, args) {
+        Ok(response) if response.ok => {
+            match response.result {
+                serde_json::Value::Null => {}
+                serde_json::Value::String(result) => println!("{}", result),
+                result => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&result).unwrap_or_default()
+                ),
             }
+            0
+        }
+        Ok(response) => {
+            eprintln!("[IPC]: {}", response.error.unwrap_or_default());
+            1
+        }
+        Err(e) => {
+            eprintln!("[IPC]: {}", e);
+            2
         }
     }
 }
 
+/// Call `on_change` once `path` has been written. Editors often save in
+/// several steps, so events within a short delay are folded into one call.
+fn watch_file(path: &Path, on_change: impl Fn() + 'static) -> Option<gio::FileMonitor> {
+    let monitor = match gio::File::for_path(path)
+        .monitor_file(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE)
+    {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            eprintln!("Failed to watch {:?}: {}", path, e);
+            return None;
+        }
+    };
+
+    let pending = Rc::new(Cell::new(false));
+    let on_change = Rc::new(on_change);
+    monitor.connect_changed(move |_, _, _, event| {
+        let written = matches!(
+            event,
+            gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created
+        );
+        if !written || pending.get() {
+            return;
+        }
+
+        pending.set(true);
+        let pending = Rc::clone(&pending);
+        let on_change = Rc::clone(&on_change);
+        gtk::glib::timeout_add_local_once(std::time::Duration::from_millis(200), move || {
+            pending.set(false);
+            on_change();
+        });
+    });
+
+    Some(monitor)
+}
+
 // Apply current css/scss to application
 // Reloads style if runned again
 fn apply_css_to_gtk() {
@@ -501,86 +774,212 @@ fn apply_css_to_gtk() {
         }
     };
 
-    let provider = gtk::CssProvider::new();
-    provider.load_from_data(&css);
+    CSS_PROVIDER.with_borrow_mut(|current| {
+        if let Some(provider) = current {
+            provider.load_from_data(&css);
+            println!("CSS reloaded");
+            return;
+        }
 
-    if let Some(display) = gtk::gdk::Display::default() {
-        gtk::style_context_add_provider_for_display(&display, &provider, 950);
-        println!("CSS applied to GTK");
-    } else {
-        eprintln!("Failed to get default GTK display");
-    }
+        let provider = gtk::CssProvider::new();
+        provider.load_from_data(&css);
+
+        if let Some(display) = gtk::gdk::Display::default() {
+            gtk::style_context_add_provider_for_display(&display, &provider, 950);
+            println!("CSS applied to GTK");
+            *current = Some(provider);
+        } else {
+            eprintln!("Failed to get default GTK display");
+        }
+    });
 }
 
-fn expand_tilde(path: &str) -> PathBuf {
-    if let Some(stripped) = path.strip_prefix("~/") {
-        let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        PathBuf::from(home).join(stripped)
-    } else if path == "~" {
-        PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-    } else {
-        PathBuf::from(path)
+/// Watch the style files and their imports, re-applying CSS when one of
+/// them changes. Imports are collected again after every change.
+fn watch_style(monitors: &Rc<RefCell<Vec<gio::FileMonitor>>>) {
+    let files = config::style_files();
+    let mut new_monitors = Vec::new();
+
+    for file in &files {
+        let monitors = Rc::clone(monitors);
+        let monitor = watch_file(file, move || {
+            apply_css_to_gtk();
+            watch_style(&monitors);
+        });
+        new_monitors.extend(monitor);
     }
+
+    *monitors.borrow_mut() = new_monitors;
 }
 
-#[derive(Debug)]
-struct IpcMessage {
-    command: String,
-    target: String,
+/// A bar window together with the modules built into it.
+struct Bar {
+    name: String,
+    window: gtk::Window,
+    open_on_launch: bool,
+    /// Monitor the bar is bound to through its `output` option.
+    monitor: Option<gtk::gdk::Monitor>,
+    layer: gtk4_layer_shell::Layer,
+    /// What to do while a fullscreen window covers the bar's monitor.
+    on_fullscreen: String,
+    covered: Cell<bool>,
+    autohide: Option<autohide::AutoHide>,
+    modules: Vec<modules::NamedModule>,
 }
 
-impl IpcMessage {
-    fn parse(line: &str) -> Option<Self> {
-        let mut parts = line.splitn(2, ' ');
-        let command = parts.next()?.to_string();
-        let target = parts.next()?.to_string();
-        Some(IpcMessage { command, target })
+impl Bar {
+    /// Present the window, refreshing modules that may be stale while hidden.
+    fn show(&self) {
+        if !self.window.is_visible() {
+            self.modules.iter().for_each(|module| module.update());
+        }
+        match &self.autohide {
+            Some(autohide) => autohide.open(),
+            None => self.window.present(),
+        }
+        self.emit_state(true);
     }
-}
 
-/// Spawns a background thread that listens on the Unix socket and forwards
-/// (command, target) string pairs through an mpsc channel.
-/// Only plain Strings cross the thread boundary — no GTK types involved.
-fn ipc_listener(tx: mpsc::Sender<(String, String)>) {
-    let path = "/tmp/riftbar.sock";
-    let _ = fs::remove_file(path);
-    let listener = UnixListener::bind(path).expect("Failed to bind IPC socket");
-
-    std::thread::spawn(move || {
-        for stream in listener.incoming() {
-            let stream = match stream {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("IPC accept error: {}", e);
-                    continue;
-                }
-            };
+    fn hide(&self) {
+        match &self.autohide {
+            Some(autohide) => autohide.close(),
+            None => self.window.set_visible(false),
+        }
+        self.emit_state(false);
+    }
 
-            let reader = BufReader::new(stream);
-            for line in reader.lines() {
-                let line = match line {
-                    Ok(l) => l,
-                    Err(e) => {
-                        eprintln!("IPC read error: {}", e);
-                        break;
-                    }
-                };
+    /// Description of the bar for IPC queries.
+    fn state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "monitor": self.connector().map(|connector| connector.to_string()),
+            "open": self.is_open(),
+            "visible": self.window.is_visible(),
+            "autohide": self.autohide.is_some(),
+            "modules": self.modules.len(),
+        })
+    }
 
-                match IpcMessage::parse(&line) {
-                    Some(msg) => {
-                        println!("IPC: command='{}' target='{}'", msg.command, msg.target);
-                        // Only Strings cross the thread boundary — Send safe
-                        let _ = tx.send((msg.command, msg.target));
-                    }
-                    None => eprintln!("Malformed IPC message: {:?}", line),
+    fn emit_state(&self, open: bool) {
+        ipc::emit(
+            "bar",
+            serde_json::json!({
+                "name": self.name,
+                "monitor": self.connector().map(|connector| connector.to_string()),
+                "open": open,
+            }),
+        );
+    }
+
+    /// Hide or lower the bar while its monitor shows a fullscreen window.
+    fn set_fullscreen(&self, fullscreen: bool) {
+        if self.covered.get() == fullscreen {
+            return;
+        }
+
+        match self.on_fullscreen.as_str() {
+            "hide" => {
+                if !fullscreen {
+                    self.show();
+                } else if self.is_open() {
+                    self.hide();
+                } else {
+                    return;
                 }
             }
+            "lower" => {
+                let layer = if fullscreen {
+                    gtk4_layer_shell::Layer::Bottom
+                } else {
+                    self.layer
+                };
+                self.window.set_layer(layer);
+            }
+            _ => return,
         }
-    });
+
+        self.covered.set(fullscreen);
+    }
+
+    /// Connector name of the monitor the bar is on.
+    fn connector(&self) -> Option<gtk::glib::GString> {
+        let monitor = match &self.monitor {
+            Some(monitor) => monitor.clone(),
+            None => {
+                let surface = self.window.surface()?;
+                WidgetExt::display(&self.window).monitor_at_surface(&surface)?
+            }
+        };
+        monitor.connector()
+    }
+
+    fn is_open(&self) -> bool {
+        match &self.autohide {
+            Some(autohide) => autohide.is_open(),
+            None => self.window.is_visible(),
+        }
+    }
+
+    /// Stop all modules and close the window for good.
+    fn destroy(&self) {
+        self.modules.iter().for_each(|module| module.teardown());
+        if let Some(autohide) = &self.autohide {
+            autohide.destroy();
+        }
+        self.window.destroy();
+    }
 }
 
-fn ipc_writer(command: &str, target: &str) -> std::io::Result<()> {
-    let mut stream = UnixStream::connect("/tmp/riftbar.sock")?;
-    writeln!(stream, "{} {}", command, target)?;
-    Ok(())
+/// Name of the IPC socket: `--instance` if given, otherwise the file name of
+/// a `-c` config followed by a hash of its full path, so bars started from
+/// different configs don't share one even when their file names match.
+fn instance_name(instance: Option<String>, config_path: &Path) -> String {
+    instance.unwrap_or_else(|| {
+        if config_path == config::Config::get_config_path() {
+            return "default".to_string();
+        }
+        // The same file must give the same name from any working directory
+        let path = std::fs::canonicalize(config_path).unwrap_or_else(|_| config_path.into());
+        // FNV-1a, stable across builds unlike `DefaultHasher`
+        let hash = path
+            .as_os_str()
+            .as_encoded_bytes()
+            .iter()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+            });
+        let stem = config_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "config".to_string());
+        format!("{}-{:08x}", stem, hash as u32)
+    })
+}
+
+/// GTK forwards a second launch with the same id to the running process,
+/// so every instance registers its own.
+fn application_id(instance: &str) -> String {
+    if instance == "default" {
+        return "com.binaryharb.RiftBar".to_string();
+    }
+
+    let mut name: String = instance
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    format!("com.binaryharb.RiftBar.{}", name)
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    if let Some(stripped) = path.strip_prefix("~/") {
+        let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(stripped)
+    } else if path == "~" {
+        PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+    } else {
+        PathBuf::from(path)
+    }
 }
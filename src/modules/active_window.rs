@@ -1,10 +1,12 @@
 // ============ modules/active_window.rs ============
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 use gtk4 as gtk;
 use gtk4::prelude::*;
 use std::env;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::os::unix::net::UnixStream;
-use std::sync::mpsc;
+use std::sync::{Arc, Mutex, mpsc};
 
 #[derive(Clone)]
 pub struct ActiveWindowConfig {
@@ -47,6 +49,9 @@ impl ActiveWindowConfig {
 
 pub struct ActiveWindowWidget {
     pub button: gtk::Button,
+    window_info: Arc<Mutex<WindowInfo>>,
+    tooltip: bool,
+    sources: SourceHandles,
 }
 
 #[derive(Clone, Debug)]
@@ -55,6 +60,31 @@ struct WindowInfo {
     title: String,
 }
 
+impl Module for ActiveWindowWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        Some(Self::new(ActiveWindowConfig::from_config(
+            &ctx.instance_config(&ctx.config.active_window),
+        )))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.button.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        state["tooltip"] = serde_json::json!(
+            self.tooltip
+                .then(|| tooltip_text(&self.window_info.lock().unwrap()))
+        );
+        state
+    }
+}
+
 impl ActiveWindowWidget {
     pub fn new(mut config: ActiveWindowConfig) -> Self {
         if config.no_window_format.is_empty() {
@@ -77,45 +107,37 @@ impl ActiveWindowWidget {
             },
         );
 
-        let widget = Self { button };
+        let widget = Self {
+            button,
+            window_info: Arc::new(Mutex::new(WindowInfo {
+                class: String::new(),
+                title: String::new(),
+            })),
+            tooltip: config.tooltip,
+            sources: SourceHandles::default(),
+        };
         widget.start_updates(config);
         widget
     }
 
-    pub fn widget(&self) -> &gtk::Button {
-        &self.button
-    }
-
     fn start_updates(&self, config: ActiveWindowConfig) {
         let button = self.button.clone();
         let (sender, receiver) = mpsc::channel::<WindowInfo>();
 
         let length_lim = config.length_lim;
 
-        let window_info = std::sync::Arc::new(std::sync::Mutex::new(WindowInfo {
-            class: String::new(),
-            title: String::new(),
-        }));
-
         // Set up tooltip if enabled
         if config.tooltip {
             button.set_has_tooltip(true);
-            let window_info_tooltip = window_info.clone();
+            let window_info_tooltip = self.window_info.clone();
 
             button.connect_query_tooltip(move |_, _, _, _, tooltip| {
-                let info = window_info_tooltip.lock().unwrap();
-                if !info.title.is_empty() {
-                    let tooltip_text = format!("{}\n{}", info.class, info.title);
-                    tooltip.set_text(Some(&tooltip_text));
-                    true
-                } else {
-                    tooltip.set_text(Some("No active window"));
-                    true
-                }
+                tooltip.set_text(Some(&tooltip_text(&window_info_tooltip.lock().unwrap())));
+                true
             });
         }
 
-        let window_info_clone = window_info.clone();
+        let window_info_clone = self.window_info.clone();
 
         // Spawn window monitor thread
         std::thread::spawn(move || {
@@ -125,52 +147,61 @@ impl ActiveWindowWidget {
                     let _ = sender.send(WindowInfo { class, title });
                 }
 
-                // Listen for window changes
-                let _ = monitor.listen(|class, title| {
-                    let _ = sender.send(WindowInfo { class, title });
-                });
+                // Listen for window changes until the widget is gone
+                let _ =
+                    monitor.listen(|class, title| sender.send(WindowInfo { class, title }).is_ok());
             } else {
                 eprintln!("[ActiveWindow]: Failed to initialize window monitor");
             }
         });
 
         // Poll for updates
-        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-            if let Ok(info) = receiver.try_recv() {
-                // Update shared window info
-                {
-                    let mut shared_info = window_info_clone.lock().unwrap();
-                    shared_info.class = info.class.clone();
-                    shared_info.title = info.title.clone();
+        self.sources.push(glib::timeout_add_local(
+            std::time::Duration::from_millis(100),
+            move || {
+                if let Ok(info) = receiver.try_recv() {
+                    // Update shared window info
+                    {
+                        let mut shared_info = window_info_clone.lock().unwrap();
+                        shared_info.class = info.class.clone();
+                        shared_info.title = info.title.clone();
+                    }
+
+                    // Determine display text
+                    let display_text = if !info.title.is_empty() {
+                        config
+                            .format
+                            .clone()
+                            .as_deref()
+                            .unwrap_or("{title}")
+                            .replace("{title}", &info.title)
+                            .replace("{class}", &info.class)
+                    } else {
+                        config.no_window_format.clone()
+                    };
+
+                    // Truncate if needed
+                    let label =
+                        if length_lim != 0 && display_text.chars().count() > length_lim as usize {
+                            crate::shared::take_chars(&display_text, length_lim).to_string() + "…"
+                        } else {
+                            display_text
+                        };
+
+                    button.set_label(&label);
                 }
 
-                // Determine display text
-                let display_text = if !info.title.is_empty() {
-                    config
-                        .format
-                        .clone()
-                        .as_deref()
-                        .unwrap_or("{title}")
-                        .replace("{title}", &info.title)
-                        .replace("{class}", &info.class)
-                } else {
-                    config.no_window_format.clone()
-                };
-
-                // Truncate if needed
-                let label = if length_lim != 0 && display_text.chars().count() > length_lim as usize
-                {
-                    crate::shared::take_chars(&display_text, length_lim).to_string() + "…"
-                } else {
-                    display_text
-                };
-
-                button.set_label(&label);
-            }
+                glib::ControlFlow::Continue
+            },
+        ));
+    }
+}
 
-            glib::ControlFlow::Continue
-        });
+fn tooltip_text(info: &WindowInfo) -> String {
+    if info.title.is_empty() {
+        return "No active window".to_string();
     }
+    format!("{}\n{}", info.class, info.title)
 }
 
 // ============ Window Monitor Implementation ============
@@ -246,7 +277,7 @@ impl WindowMonitor {
 
     fn listen<F>(&mut self, callback: F) -> std::io::Result<()>
     where
-        F: FnMut(String, String),
+        F: FnMut(String, String) -> bool,
     {
         match self.compositor {
             Compositor::Hyprland => self.listen_hyprland(callback),
@@ -260,7 +291,7 @@ impl WindowMonitor {
 
     fn listen_hyprland<F>(&mut self, mut callback: F) -> std::io::Result<()>
     where
-        F: FnMut(String, String),
+        F: FnMut(String, String) -> bool,
     {
         let reader = self.reader.as_mut().ok_or_else(|| {
             std::io::Error::new(std::io::ErrorKind::NotConnected, "No connection")
@@ -278,7 +309,9 @@ impl WindowMonitor {
                 if parts.len() >= 2 {
                     let class = parts[0].to_string();
                     let title = parts[1].to_string();
-                    callback(class, title);
+                    if !callback(class, title) {
+                        break;
+                    }
                 }
             }
         }
@@ -287,7 +320,7 @@ impl WindowMonitor {
 
     fn listen_sway<F>(&mut self, mut callback: F) -> std::io::Result<()>
     where
-        F: FnMut(String, String),
+        F: FnMut(String, String) -> bool,
     {
         let reader = self.reader.as_mut().ok_or_else(|| {
             std::io::Error::new(std::io::ErrorKind::NotConnected, "No connection")
@@ -310,7 +343,9 @@ impl WindowMonitor {
                 if json.contains("\"change\":\"focus\"") {
                     let class = extract_field(json, "app_id").unwrap_or_default();
                     let title = extract_field(json, "name").unwrap_or_default();
-                    callback(class, title);
+                    if !callback(class, title) {
+                        return Ok(());
+                    }
                 }
             }
         }
@@ -0,0 +1,376 @@
+// ============ modules/audio/devices.rs ============
+use super::pulse;
+use super::widget::{AudioBackend, DeviceKind};
+use gtk4 as gtk;
+use gtk4::gio;
+use gtk4::prelude::*;
+use serde_json::Value;
+use std::io;
+use std::process::Command;
+use std::sync::mpsc;
+
+/// A sink or source listed in the device popover.
+#[derive(Clone, Debug)]
+pub struct AudioDevice {
+    /// What the backend addresses the device by, the node id for wpctl.
+    pub id: String,
+    pub description: String,
+    pub volume: i32,
+    pub muted: bool,
+    pub is_default: bool,
+}
+
+pub fn list_devices(backend: &AudioBackend, kind: DeviceKind) -> Vec<AudioDevice> {
+    let devices = match backend {
+        AudioBackend::Native => list_native(kind),
+        AudioBackend::PipeWire => list_pipewire(kind),
+        AudioBackend::PulseAudio => list_pulseaudio(kind),
+        AudioBackend::Unknown => Ok(Vec::new()),
+    };
+
+    devices.unwrap_or_else(|e| {
+        eprintln!("[{}] failed to list devices: {}", kind.name(), e);
+        Vec::new()
+    })
+}
+
+pub fn set_default_device(backend: &AudioBackend, kind: DeviceKind, id: &str) {
+    match backend {
+        AudioBackend::Native => {
+            let result = pulse::Connection::connect()
+                .and_then(|mut connection| kind.set_default(&mut connection, id));
+            if let Err(e) = result {
+                eprintln!("[{}] failed to set the default device: {}", kind.name(), e);
+            }
+        }
+        AudioBackend::PipeWire => {
+            let _ = Command::new("wpctl").args(["set-default", id]).output();
+        }
+        AudioBackend::PulseAudio => {
+            let _ = Command::new("pactl")
+                .args([&format!("set-default-{}", kind.pactl_object()), id])
+                .output();
+        }
+        AudioBackend::Unknown => {}
+    }
+}
+
+pub fn set_device_volume(backend: &AudioBackend, kind: DeviceKind, id: &str, volume: i32) {
+    match backend {
+        AudioBackend::Native => {
+            let result = pulse::Connection::connect().and_then(|mut connection| {
+                let device = kind.read(&mut connection, id)?;
+                let channels = vec![pulse::from_percent(volume); device.volume.len()];
+                kind.set_volume(&mut connection, id, &channels)
+            });
+            if let Err(e) = result {
+                eprintln!(
+                    "[{}] failed to set the volume of {}: {}",
+                    kind.name(),
+                    id,
+                    e
+                );
+            }
+        }
+        AudioBackend::PipeWire => {
+            let volume = format!("{:.2}", volume as f64 / 100.0);
+            let _ = Command::new("wpctl")
+                .args(["set-volume", id, &volume])
+                .output();
+        }
+        AudioBackend::PulseAudio => {
+            let _ = Command::new("pactl")
+                .args([
+                    &format!("set-{}-volume", kind.pactl_object()),
+                    id,
+                    &format!("{}%", volume),
+                ])
+                .output();
+        }
+        AudioBackend::Unknown => {}
+    }
+}
+
+/// Start a thread passing queued values to `write` in order, and return the
+/// sender to queue them on. Values queued while a write runs are merged so
+/// only the newest one per key is written, a slider drag doesn't pile up
+/// writes. The thread ends once every sender is dropped.
+pub fn latest_writer<K, V>(mut write: impl FnMut(&K, V) + Send + 'static) -> mpsc::Sender<(K, V)>
+where
+    K: PartialEq + Send + 'static,
+    V: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel::<(K, V)>();
+    std::thread::spawn(move || {
+        while let Ok(first) = receiver.recv() {
+            let mut latest = vec![first];
+            for (key, value) in receiver.try_iter() {
+                match latest.iter_mut().find(|(latest_key, _)| *latest_key == key) {
+                    Some(entry) => entry.1 = value,
+                    None => latest.push((key, value)),
+                }
+            }
+            for (key, value) in latest {
+                write(&key, value);
+            }
+        }
+    });
+    sender
+}
+
+fn list_native(kind: DeviceKind) -> io::Result<Vec<AudioDevice>> {
+    let mut connection = pulse::Connection::connect()?;
+    let server = connection.server_info()?;
+    let (devices, default) = match kind {
+        DeviceKind::Sink => (connection.sinks()?, server.default_sink),
+        DeviceKind::Source => (connection.sources()?, server.default_source),
+    };
+
+    Ok(devices
+        .into_iter()
+        .map(|device| AudioDevice {
+            volume: device.percent(),
+            muted: device.muted,
+            is_default: device.name == default,
+            id: device.name,
+            description: device.description,
+        })
+        .collect())
+}
+
+/// Parse the `Audio` part of `wpctl status`:
+///
+/// ```text
+///  ├─ Sinks:
+///  │  *   46. Built-in Audio Analog Stereo        [vol: 0.40]
+///  │      52. HDMI / DisplayPort                  [vol: 1.00 MUTED]
+/// ```
+fn list_pipewire(kind: DeviceKind) -> io::Result<Vec<AudioDevice>> {
+    let output = Command::new("wpctl").arg("status").output()?;
+    let status = String::from_utf8_lossy(&output.stdout);
+    let section = match kind {
+        DeviceKind::Sink => "Sinks:",
+        DeviceKind::Source => "Sources:",
+    };
+
+    let mut devices = Vec::new();
+    let mut in_audio = false;
+    let mut in_section = false;
+    for line in status.lines() {
+        // Top level headers: Audio, Video, Settings
+        if !line.starts_with(' ') && !line.is_empty() {
+            in_audio = line.trim() == "Audio";
+            in_section = false;
+            continue;
+        }
+        if !in_audio {
+            continue;
+        }
+        if line.contains("├─") || line.contains("└─") {
+            in_section = line.trim_end().ends_with(section);
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+
+        let entry = line.trim_start_matches([' ', '│']).trim();
+        let (is_default, entry) = match entry.strip_prefix('*') {
+            Some(entry) => (true, entry.trim_start()),
+            None => (false, entry),
+        };
+        let Some((id, rest)) = entry.split_once(". ") else {
+            continue;
+        };
+        if id.parse::<u32>().is_err() {
+            continue;
+        }
+
+        let (description, volume) = match rest.rsplit_once("[vol:") {
+            Some((description, volume)) => (description.trim(), volume),
+            None => (rest.trim(), ""),
+        };
+        let muted = volume.contains("MUTED");
+        let volume = volume
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.trim_end_matches(']').parse::<f32>().ok())
+            .map(|v| (v * 100.0).round() as i32)
+            .unwrap_or(0);
+
+        devices.push(AudioDevice {
+            id: id.to_string(),
+            description: description.to_string(),
+            volume,
+            muted,
+            is_default,
+        });
+    }
+
+    Ok(devices)
+}
+
+fn list_pulseaudio(kind: DeviceKind) -> io::Result<Vec<AudioDevice>> {
+    let object = kind.pactl_object();
+    let default = Command::new("pactl")
+        .arg(format!("get-default-{}", object))
+        .output()?;
+    let default = String::from_utf8_lossy(&default.stdout).trim().to_string();
+
+    let output = Command::new("pactl")
+        .args(["-f", "json", "list", &format!("{}s", object)])
+        .output()?;
+    let list: Vec<Value> = serde_json::from_slice(&output.stdout).map_err(io::Error::other)?;
+
+    Ok(list
+        .iter()
+        // Sources that monitor a sink aren't inputs
+        .filter(|device| {
+            device["monitor_of_sink"]
+                .as_str()
+                .is_none_or(|sink| sink == "n/a")
+        })
+        .map(|device| {
+            let id = device["name"].as_str().unwrap_or_default().to_string();
+            let channels: Vec<u32> = device["volume"]
+                .as_object()
+                .map(|volume| {
+                    volume
+                        .values()
+                        .filter_map(|channel| channel["value"].as_u64())
+                        .map(|value| value as u32)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            AudioDevice {
+                description: device["description"].as_str().unwrap_or(&id).to_string(),
+                volume: pulse::percent(&channels),
+                muted: device["mute"].as_bool().unwrap_or(false),
+                is_default: id == default,
+                id,
+            }
+        })
+        .collect())
+}
+
+/// Show a popover under `button` listing outputs and inputs, to pick the
+/// default of each and set their volume. It is built again on every open.
+pub fn show_device_popover(button: &gtk::Button, backend: AudioBackend, max_volume: i32) {
+    let button = button.clone();
+    glib::spawn_future_local(async move {
+        let listing = gio::spawn_blocking({
+            let backend = backend.clone();
+            move || {
+                (
+                    list_devices(&backend, DeviceKind::Sink),
+                    list_devices(&backend, DeviceKind::Source),
+                )
+            }
+        })
+        .await;
+        let Ok((sinks, sources)) = listing else {
+            return;
+        };
+
+        // One writer for the popover, it ends when the sliders are dropped
+        let volumes = latest_writer({
+            let backend = backend.clone();
+            move |(kind, id): &(DeviceKind, String), volume| {
+                set_device_volume(&backend, *kind, id, volume)
+            }
+        });
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        content.add_css_class("audio-devices");
+        let sections = [
+            ("Output", &sinks, DeviceKind::Sink),
+            ("Input", &sources, DeviceKind::Source),
+        ];
+        for (title, devices, kind) in sections {
+            append_section(
+                &content, title, devices, &backend, kind, max_volume, &volumes,
+            );
+        }
+
+        let popover = gtk::Popover::new();
+        popover.add_css_class("audio-popover");
+        popover.set_child(Some(&content));
+        popover.set_parent(&button);
+        popover.connect_closed(|popover| {
+            let popover = popover.clone();
+            glib::idle_add_local_once(move || popover.unparent());
+        });
+        popover.popup();
+    });
+}
+
+fn append_section(
+    content: &gtk::Box,
+    title: &str,
+    devices: &[AudioDevice],
+    backend: &AudioBackend,
+    kind: DeviceKind,
+    max_volume: i32,
+    volumes: &mpsc::Sender<((DeviceKind, String), i32)>,
+) {
+    if devices.is_empty() {
+        return;
+    }
+
+    let header = gtk::Label::new(Some(title));
+    header.add_css_class("audio-devices-header");
+    header.set_xalign(0.0);
+    content.append(&header);
+
+    let mut group: Option<gtk::CheckButton> = None;
+    for device in devices {
+        let row = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        row.add_css_class("audio-device");
+        if device.is_default {
+            row.add_css_class("default");
+        }
+        if device.muted {
+            row.add_css_class("muted");
+        }
+
+        let check = gtk::CheckButton::with_label(&device.description);
+        check.set_group(group.as_ref());
+        check.set_active(device.is_default);
+        group.get_or_insert_with(|| check.clone());
+
+        let row_clone = row.clone();
+        let backend_default = backend.clone();
+        let id = device.id.clone();
+        check.connect_toggled(move |check| {
+            // Fires for the device losing the default as well
+            if !check.is_active() {
+                row_clone.remove_css_class("default");
+                return;
+            }
+            row_clone.add_css_class("default");
+            let backend = backend_default.clone();
+            let id = id.clone();
+            std::thread::spawn(move || set_default_device(&backend, kind, &id));
+        });
+
+        let max_volume = max_volume as f64;
+        let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, max_volume, 1.0);
+        scale.add_css_class("audio-device-volume");
+        scale.set_value(device.volume as f64);
+        scale.set_hexpand(true);
+
+        // `change-value` only fires for the user, not for `set_value` above
+        let volumes = volumes.clone();
+        let id = device.id.clone();
+        scale.connect_change_value(move |_, _, value| {
+            let volume = value.clamp(0.0, max_volume).round() as i32;
+            let _ = volumes.send(((kind, id.clone()), volume));
+            gtk4::glib::Propagation::Proceed
+        });
+
+        row.append(&check);
+        row.append(&scale);
+        content.append(&row);
+    }
+}
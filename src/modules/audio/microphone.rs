@@ -0,0 +1,33 @@
+// ============ modules/audio/microphone.rs ============
+use super::widget::{AudioConfig, AudioWidget, DeviceKind};
+use crate::modules::{Module, ModuleContext};
+use gtk4 as gtk;
+
+/// The audio widget bound to the default source instead of the sink.
+pub struct MicrophoneWidget {
+    inner: AudioWidget,
+}
+
+impl Module for MicrophoneWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        let config = ctx.instance_config(&ctx.config.microphone);
+        Some(Self {
+            inner: AudioWidget::new(
+                AudioConfig::from_microphone_config(&config),
+                DeviceKind::Source,
+            ),
+        })
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.inner.widget()
+    }
+
+    fn teardown(&self) {
+        self.inner.teardown();
+    }
+
+    fn state(&self) -> serde_json::Value {
+        self.inner.state()
+    }
+}
@@ -0,0 +1,437 @@
+// ============ modules/audio/mixer.rs ============
+use super::devices::latest_writer;
+use super::pulse;
+use super::widget::AudioBackend;
+use gtk4 as gtk;
+use gtk4::prelude::*;
+use serde_json::Value;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io;
+use std::process::Command;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+
+/// How often pactl is asked for streams, the native backend gets events.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A playback stream listed in the mixer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioStream {
+    pub index: u32,
+    pub app_name: String,
+    pub icon_name: Option<String>,
+    /// What is playing, `media.name`.
+    pub title: String,
+    pub volume: i32,
+    /// Channels a volume is set for, a stream keeps its count.
+    pub channels: usize,
+    pub muted: bool,
+}
+
+impl AudioStream {
+    fn from_properties(
+        index: u32,
+        properties: &HashMap<String, String>,
+        channels: &[u32],
+        muted: bool,
+    ) -> Self {
+        let property = |key: &str| properties.get(key).filter(|value| !value.is_empty());
+        Self {
+            index,
+            app_name: property("application.name")
+                .or_else(|| property("application.process.binary"))
+                .cloned()
+                .unwrap_or_else(|| format!("Stream {}", index)),
+            icon_name: property("application.icon_name").cloned(),
+            title: property("media.name").cloned().unwrap_or_default(),
+            volume: pulse::percent(channels),
+            channels: channels.len(),
+            muted,
+        }
+    }
+}
+
+/// Whether `backend` can list streams. wpctl has no stable way to, and
+/// PipeWire setups with pipewire-pulse use the native backend anyway.
+pub fn supports_streams(backend: &AudioBackend) -> bool {
+    matches!(backend, AudioBackend::Native | AudioBackend::PulseAudio)
+}
+
+pub fn list_streams(backend: &AudioBackend) -> io::Result<Vec<AudioStream>> {
+    match backend {
+        AudioBackend::Native => list_native(&mut pulse::Connection::connect()?),
+        AudioBackend::PulseAudio => list_pulseaudio(),
+        AudioBackend::PipeWire | AudioBackend::Unknown => Ok(Vec::new()),
+    }
+}
+
+/// Set the volume of all `channels` of stream `index`. The native backend
+/// reuses `connection`, opening it when there is none yet.
+pub fn set_stream_volume(
+    backend: &AudioBackend,
+    connection: &mut Option<pulse::Connection>,
+    index: u32,
+    channels: usize,
+    volume: i32,
+) {
+    match backend {
+        AudioBackend::Native => {
+            let result = match connection {
+                Some(connection) => Ok(connection),
+                None => pulse::Connection::connect().map(|opened| connection.insert(opened)),
+            }
+            .and_then(|connection| {
+                connection
+                    .set_sink_input_volume(index, &vec![pulse::from_percent(volume); channels])
+            });
+            if let Err(e) = result {
+                eprintln!(
+                    "[audio] failed to set the volume of stream {}: {}",
+                    index, e
+                );
+                // Connect again on the next write
+                *connection = None;
+            }
+        }
+        AudioBackend::PulseAudio => {
+            let _ = Command::new("pactl")
+                .args([
+                    "set-sink-input-volume",
+                    &index.to_string(),
+                    &format!("{}%", volume),
+                ])
+                .output();
+        }
+        AudioBackend::PipeWire | AudioBackend::Unknown => {}
+    }
+}
+
+pub fn set_stream_mute(backend: &AudioBackend, index: u32, muted: bool) {
+    match backend {
+        AudioBackend::Native => {
+            let result = pulse::Connection::connect()
+                .and_then(|mut connection| connection.set_sink_input_mute(index, muted));
+            if let Err(e) = result {
+                eprintln!("[audio] failed to mute stream {}: {}", index, e);
+            }
+        }
+        AudioBackend::PulseAudio => {
+            let _ = Command::new("pactl")
+                .args([
+                    "set-sink-input-mute",
+                    &index.to_string(),
+                    if muted { "1" } else { "0" },
+                ])
+                .output();
+        }
+        AudioBackend::PipeWire | AudioBackend::Unknown => {}
+    }
+}
+
+fn list_native(connection: &mut pulse::Connection) -> io::Result<Vec<AudioStream>> {
+    Ok(connection
+        .sink_inputs()?
+        .into_iter()
+        // Passthrough streams can't be mixed
+        .filter(|input| input.has_volume)
+        .map(|mut input| {
+            // The stream name is what clients set `media.name` from
+            input
+                .properties
+                .entry("media.name".to_string())
+                .or_insert(input.name);
+            AudioStream::from_properties(input.index, &input.properties, &input.volume, input.muted)
+        })
+        .collect())
+}
+
+fn list_pulseaudio() -> io::Result<Vec<AudioStream>> {
+    let output = Command::new("pactl")
+        .args(["-f", "json", "list", "sink-inputs"])
+        .output()?;
+    let list: Vec<Value> = serde_json::from_slice(&output.stdout).map_err(io::Error::other)?;
+
+    Ok(list
+        .iter()
+        .filter_map(|input| {
+            let index = input["index"].as_u64()? as u32;
+            let properties: HashMap<String, String> = input["properties"]
+                .as_object()
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let channels: Vec<u32> = input["volume"]
+                .as_object()?
+                .values()
+                .filter_map(|channel| channel["value"].as_u64())
+                .map(|value| value as u32)
+                .collect();
+
+            Some(AudioStream::from_properties(
+                index,
+                &properties,
+                &channels,
+                input["mute"].as_bool().unwrap_or(false),
+            ))
+        })
+        .collect())
+}
+
+/// Send the list of streams whenever it changes, until the receiver is
+/// dropped or `stopper` is stopped.
+fn watch_streams(
+    backend: AudioBackend,
+    sender: UnboundedSender<Vec<AudioStream>>,
+    stopper: pulse::Stopper,
+) {
+    std::thread::spawn(move || {
+        let result = match backend {
+            AudioBackend::Native => follow_native(&sender, &stopper),
+            AudioBackend::PulseAudio => poll_streams(&backend, &sender),
+            AudioBackend::PipeWire | AudioBackend::Unknown => {
+                let _ = sender.send(Vec::new());
+                Ok(())
+            }
+        };
+        if let Err(e) = result
+            && !stopper.is_stopped()
+        {
+            eprintln!("[audio] stopped following streams: {}", e);
+        }
+    });
+}
+
+fn follow_native(
+    sender: &UnboundedSender<Vec<AudioStream>>,
+    stopper: &pulse::Stopper,
+) -> io::Result<()> {
+    let mut connection = pulse::Connection::connect()?;
+    stopper.watch(&connection)?;
+    connection.subscribe(pulse::SUBSCRIBE_SINK_INPUT)?;
+
+    let mut last = None;
+    loop {
+        let streams = list_native(&mut connection)?;
+        if last.as_ref() != Some(&streams) {
+            if sender.send(streams.clone()).is_err() {
+                return Ok(());
+            }
+            last = Some(streams);
+        }
+        while connection.next_event()?.facility != pulse::Facility::SinkInput {}
+    }
+}
+
+fn poll_streams(
+    backend: &AudioBackend,
+    sender: &UnboundedSender<Vec<AudioStream>>,
+) -> io::Result<()> {
+    // Unchanged lists are sent too, the failing send ends the loop
+    while sender.send(list_streams(backend)?).is_ok() {
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Widgets of one stream, kept across updates so a drag isn't interrupted.
+struct StreamRow {
+    row: gtk::Box,
+    icon: gtk::Image,
+    name: gtk::Label,
+    scale: gtk::Scale,
+    mute: gtk::ToggleButton,
+    /// Sent along with every volume the slider sets.
+    channels: Rc<Cell<usize>>,
+    /// Set while the pointer holds the slider, updates would fight the drag.
+    dragging: Rc<Cell<bool>>,
+}
+
+/// Show a popover under `button` with a volume slider and a mute toggle for
+/// every playback stream, following streams as they come and go.
+pub fn show_mixer_popover(button: &gtk::Button, backend: AudioBackend, max_volume: i32) {
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    content.add_css_class("audio-mixer");
+
+    let placeholder = gtk::Label::new(Some("No streams playing"));
+    placeholder.add_css_class("audio-mixer-empty");
+    content.append(&placeholder);
+
+    let (sender, mut receiver) = unbounded_channel::<Vec<AudioStream>>();
+    let stopper = pulse::Stopper::default();
+    watch_streams(backend.clone(), sender, stopper.clone());
+
+    // One writer for the popover, it ends when the sliders are dropped
+    let volumes = latest_writer({
+        let backend = backend.clone();
+        let mut connection = None;
+        move |index: &u32, (channels, volume)| {
+            set_stream_volume(&backend, &mut connection, *index, channels, volume)
+        }
+    });
+
+    // Rows are synced when the watcher sends a change, not on a timer
+    let mut rows = HashMap::new();
+    let updates = glib::spawn_future_local({
+        let content = content.clone();
+        async move {
+            while let Some(mut streams) = receiver.recv().await {
+                while let Ok(newer) = receiver.try_recv() {
+                    streams = newer;
+                }
+                sync_rows(
+                    &content, &mut rows, &streams, &backend, max_volume, &volumes,
+                );
+                placeholder.set_visible(streams.is_empty());
+            }
+        }
+    });
+
+    let popover = gtk::Popover::new();
+    popover.add_css_class("audio-popover");
+    popover.set_child(Some(&content));
+    popover.set_parent(button);
+    popover.connect_closed(move |popover| {
+        updates.abort();
+        stopper.stop();
+        let popover = popover.clone();
+        glib::idle_add_local_once(move || popover.unparent());
+    });
+    popover.popup();
+}
+
+fn sync_rows(
+    content: &gtk::Box,
+    rows: &mut HashMap<u32, StreamRow>,
+    streams: &[AudioStream],
+    backend: &AudioBackend,
+    max_volume: i32,
+    volumes: &mpsc::Sender<(u32, (usize, i32))>,
+) {
+    rows.retain(|index, row| {
+        let alive = streams.iter().any(|stream| stream.index == *index);
+        if !alive {
+            content.remove(&row.row);
+        }
+        alive
+    });
+
+    for stream in streams {
+        let row = rows
+            .entry(stream.index)
+            .or_insert_with(|| stream_row(content, stream.index, backend, max_volume, volumes));
+
+        row.icon.set_icon_name(Some(
+            stream
+                .icon_name
+                .as_deref()
+                .unwrap_or("audio-x-generic-symbolic"),
+        ));
+        row.name.set_text(&stream.app_name);
+        row.row.set_tooltip_text(Some(&stream.title));
+        row.channels.set(stream.channels);
+        if !row.dragging.get() && row.scale.value().round() as i32 != stream.volume {
+            row.scale.set_value(stream.volume as f64);
+        }
+        row.mute.set_active(stream.muted);
+        if stream.muted {
+            row.row.add_css_class("muted");
+        } else {
+            row.row.remove_css_class("muted");
+        }
+    }
+}
+
+fn stream_row(
+    content: &gtk::Box,
+    index: u32,
+    backend: &AudioBackend,
+    max_volume: i32,
+    volumes: &mpsc::Sender<(u32, (usize, i32))>,
+) -> StreamRow {
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    row.add_css_class("audio-stream");
+
+    let icon = gtk::Image::new();
+    icon.add_css_class("audio-stream-icon");
+
+    let details = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    details.set_hexpand(true);
+
+    let name = gtk::Label::new(None);
+    name.add_css_class("audio-stream-name");
+    name.set_xalign(0.0);
+
+    let max_volume = max_volume as f64;
+    let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, max_volume, 1.0);
+    scale.add_css_class("audio-stream-volume");
+    scale.set_hexpand(true);
+
+    let mute = gtk::ToggleButton::new();
+    mute.add_css_class("audio-stream-mute");
+    mute.set_icon_name("audio-volume-muted-symbolic");
+
+    // Sees presses before the slider claims them for its drag
+    let dragging = Rc::new(Cell::new(false));
+    let pointer = gtk::EventControllerLegacy::new();
+    pointer.set_propagation_phase(gtk::PropagationPhase::Capture);
+    pointer.connect_event({
+        let dragging = dragging.clone();
+        move |_, event| {
+            match event.event_type() {
+                gtk::gdk::EventType::ButtonPress | gtk::gdk::EventType::TouchBegin => {
+                    dragging.set(true)
+                }
+                gtk::gdk::EventType::ButtonRelease
+                | gtk::gdk::EventType::TouchEnd
+                | gtk::gdk::EventType::TouchCancel => dragging.set(false),
+                _ => {}
+            }
+            gtk4::glib::Propagation::Proceed
+        }
+    });
+    scale.add_controller(pointer);
+
+    // `change-value` and `clicked` only fire for the user, so values set by
+    // `sync_rows` aren't sent back
+    let channels = Rc::new(Cell::new(0));
+    let volumes = volumes.clone();
+    scale.connect_change_value({
+        let channels = channels.clone();
+        move |_, _, value| {
+            let volume = value.clamp(0.0, max_volume).round() as i32;
+            let _ = volumes.send((index, (channels.get(), volume)));
+            gtk4::glib::Propagation::Proceed
+        }
+    });
+
+    let backend_mute = backend.clone();
+    mute.connect_clicked(move |mute| {
+        let backend = backend_mute.clone();
+        let muted = mute.is_active();
+        std::thread::spawn(move || set_stream_mute(&backend, index, muted));
+    });
+
+    details.append(&name);
+    details.append(&scale);
+    row.append(&icon);
+    row.append(&details);
+    row.append(&mute);
+    content.append(&row);
+
+    StreamRow {
+        row,
+        icon,
+        name,
+        scale,
+        mute,
+        channels,
+        dragging,
+    }
+}
@@ -0,0 +1,11 @@
+// ============ modules/audio/mod.rs ============
+pub mod devices;
+pub mod microphone;
+pub mod mixer;
+pub mod pulse;
+pub mod widget;
+
+pub use devices::*;
+pub use microphone::*;
+pub use mixer::*;
+pub use widget::*;
@@ -0,0 +1,753 @@
+// ============ modules/audio/pulse.rs ============
+//! Minimal client for the PulseAudio native protocol, which PipeWire serves
+//! through pipewire-pulse as well. Only control packets are used: device
+//! info, volume and mute changes, and subscription events.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Write},
+    net::Shutdown,
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+const PROTOCOL_VERSION: u32 = 32;
+const CONTROL_CHANNEL: u32 = u32::MAX;
+const INVALID_INDEX: u32 = u32::MAX;
+const COOKIE_LENGTH: usize = 256;
+
+/// Names the server resolves to the default devices.
+pub const DEFAULT_SINK: &str = "@DEFAULT_SINK@";
+pub const DEFAULT_SOURCE: &str = "@DEFAULT_SOURCE@";
+
+/// Volume of a channel at 100%.
+pub const VOLUME_NORM: u32 = 0x10000;
+
+const COMMAND_ERROR: u32 = 0;
+const COMMAND_REPLY: u32 = 2;
+const COMMAND_AUTH: u32 = 8;
+const COMMAND_SET_CLIENT_NAME: u32 = 9;
+const COMMAND_GET_SERVER_INFO: u32 = 20;
+const COMMAND_GET_SINK_INFO: u32 = 21;
+const COMMAND_GET_SINK_INFO_LIST: u32 = 22;
+const COMMAND_GET_SOURCE_INFO: u32 = 23;
+const COMMAND_GET_SOURCE_INFO_LIST: u32 = 24;
+const COMMAND_GET_SINK_INPUT_INFO_LIST: u32 = 30;
+const COMMAND_SUBSCRIBE: u32 = 35;
+const COMMAND_SET_SINK_VOLUME: u32 = 36;
+const COMMAND_SET_SINK_INPUT_VOLUME: u32 = 37;
+const COMMAND_SET_SOURCE_VOLUME: u32 = 38;
+const COMMAND_SET_SINK_MUTE: u32 = 39;
+const COMMAND_SET_SOURCE_MUTE: u32 = 40;
+const COMMAND_SET_DEFAULT_SINK: u32 = 44;
+const COMMAND_SET_DEFAULT_SOURCE: u32 = 45;
+const COMMAND_SUBSCRIBE_EVENT: u32 = 66;
+const COMMAND_SET_SINK_INPUT_MUTE: u32 = 69;
+
+/// Event facilities to subscribe to, combined into a mask.
+pub const SUBSCRIBE_SINK: u32 = 0x0001;
+pub const SUBSCRIBE_SOURCE: u32 = 0x0002;
+pub const SUBSCRIBE_SINK_INPUT: u32 = 0x0004;
+pub const SUBSCRIBE_SERVER: u32 = 0x0080;
+
+/// Object an event is about, the low bits of the event type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Facility {
+    Sink,
+    Source,
+    SinkInput,
+    Server,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Event {
+    pub facility: Facility,
+}
+
+pub struct ServerInfo {
+    pub default_sink: String,
+    pub default_source: String,
+}
+
+/// A sink or source.
+#[derive(Clone, Debug)]
+pub struct Device {
+    pub name: String,
+    pub description: String,
+    /// Volume of every channel, `VOLUME_NORM` is 100%.
+    pub volume: Vec<u32>,
+    pub muted: bool,
+}
+
+/// A playback stream.
+#[derive(Clone, Debug)]
+pub struct SinkInput {
+    pub index: u32,
+    pub name: String,
+    /// `application.name`, `application.icon_name`, `media.name`, …
+    pub properties: HashMap<String, String>,
+    pub volume: Vec<u32>,
+    pub muted: bool,
+    /// Passthrough streams have no volume of their own.
+    pub has_volume: bool,
+}
+
+impl Device {
+    /// Average volume of the channels in percent.
+    pub fn percent(&self) -> i32 {
+        percent(&self.volume)
+    }
+}
+
+/// Average of `volume` in percent, rounded.
+pub fn percent(volume: &[u32]) -> i32 {
+    if volume.is_empty() {
+        return 0;
+    }
+    let sum: u64 = volume.iter().map(|v| *v as u64).sum();
+    let average = sum / volume.len() as u64;
+    ((average * 100 + VOLUME_NORM as u64 / 2) / VOLUME_NORM as u64) as i32
+}
+
+/// Raw channel volume for `percent`.
+pub fn from_percent(percent: i32) -> u32 {
+    (percent.max(0) as u64 * VOLUME_NORM as u64 / 100) as u32
+}
+
+/// Ends connections used by a watcher thread from another thread, so the
+/// thread returns instead of waiting for the next server event.
+#[derive(Clone, Default)]
+pub struct Stopper {
+    state: Arc<Mutex<StopperState>>,
+}
+
+#[derive(Default)]
+struct StopperState {
+    stopped: bool,
+    stream: Option<UnixStream>,
+}
+
+impl Stopper {
+    /// Close `connection` on `stop`, right away if that already happened.
+    pub fn watch(&self, connection: &Connection) -> io::Result<()> {
+        let stream = connection.stream.try_clone()?;
+        let mut state = self.state.lock().unwrap();
+        if state.stopped {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        state.stream = Some(stream);
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.stopped = true;
+        if let Some(stream) = state.stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.state.lock().unwrap().stopped
+    }
+}
+
+pub struct Connection {
+    stream: UnixStream,
+    version: u32,
+    next_tag: u32,
+    /// Events that arrived while waiting for a reply.
+    pending: VecDeque<Event>,
+}
+
+impl Connection {
+    /// Connect and authenticate to the sound server of this session.
+    pub fn connect() -> io::Result<Self> {
+        let path = socket_path().ok_or_else(|| other("no PulseAudio socket found"))?;
+        let stream = UnixStream::connect(path)?;
+
+        let mut connection = Self {
+            stream,
+            version: PROTOCOL_VERSION,
+            next_tag: 0,
+            pending: VecDeque::new(),
+        };
+
+        let mut auth = connection.command(COMMAND_AUTH);
+        auth.u32(PROTOCOL_VERSION).arbitrary(&cookie());
+        let mut reply = connection.request(auth)?;
+        // The upper bits carry shm flags, which we don't use
+        connection.version = PROTOCOL_VERSION.min(reply.u32()? & 0xFFFF);
+
+        let mut name = connection.command(COMMAND_SET_CLIENT_NAME);
+        if connection.version >= 13 {
+            name.proplist(&[("application.name", "riftbar")]);
+        } else {
+            name.string(Some("riftbar"));
+        }
+        connection.request(name)?;
+
+        Ok(connection)
+    }
+
+    /// Sink named `name`, `@DEFAULT_SINK@` for the default one.
+    pub fn sink(&mut self, name: &str) -> io::Result<Device> {
+        let mut command = self.command(COMMAND_GET_SINK_INFO);
+        command.u32(INVALID_INDEX).string(Some(name));
+        let mut reply = self.request(command)?;
+        reply.device()
+    }
+
+    pub fn set_sink_volume(&mut self, name: &str, volume: &[u32]) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SET_SINK_VOLUME);
+        command
+            .u32(INVALID_INDEX)
+            .string(Some(name))
+            .cvolume(volume);
+        self.request(command).map(|_| ())
+    }
+
+    pub fn set_sink_mute(&mut self, name: &str, muted: bool) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SET_SINK_MUTE);
+        command.u32(INVALID_INDEX).string(Some(name)).bool(muted);
+        self.request(command).map(|_| ())
+    }
+
+    pub fn server_info(&mut self) -> io::Result<ServerInfo> {
+        let command = self.command(COMMAND_GET_SERVER_INFO);
+        let mut reply = self.request(command)?;
+
+        reply.string()?; // package name
+        reply.string()?; // package version
+        reply.string()?; // user name
+        reply.string()?; // host name
+        reply.sample_spec()?;
+        Ok(ServerInfo {
+            default_sink: reply.string()?.unwrap_or_default(),
+            default_source: reply.string()?.unwrap_or_default(),
+        })
+    }
+
+    pub fn sinks(&mut self) -> io::Result<Vec<Device>> {
+        let devices = self.devices(COMMAND_GET_SINK_INFO_LIST)?;
+        Ok(devices.into_iter().map(|(device, _)| device).collect())
+    }
+
+    /// Sources without the monitors of sinks.
+    pub fn sources(&mut self) -> io::Result<Vec<Device>> {
+        let devices = self.devices(COMMAND_GET_SOURCE_INFO_LIST)?;
+        Ok(devices
+            .into_iter()
+            .filter(|(_, monitor_of)| *monitor_of == INVALID_INDEX)
+            .map(|(device, _)| device)
+            .collect())
+    }
+
+    pub fn sink_inputs(&mut self) -> io::Result<Vec<SinkInput>> {
+        if self.version < 21 {
+            return Err(other("listing streams needs protocol version 21"));
+        }
+
+        let command = self.command(COMMAND_GET_SINK_INPUT_INFO_LIST);
+        let mut reply = self.request(command)?;
+        let mut inputs = Vec::new();
+        while !reply.at_end() {
+            inputs.push(reply.sink_input()?);
+        }
+        Ok(inputs)
+    }
+
+    pub fn set_sink_input_volume(&mut self, index: u32, volume: &[u32]) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SET_SINK_INPUT_VOLUME);
+        command.u32(index).cvolume(volume);
+        self.request(command).map(|_| ())
+    }
+
+    pub fn set_sink_input_mute(&mut self, index: u32, muted: bool) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SET_SINK_INPUT_MUTE);
+        command.u32(index).bool(muted);
+        self.request(command).map(|_| ())
+    }
+
+    pub fn set_default_sink(&mut self, name: &str) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SET_DEFAULT_SINK);
+        command.string(Some(name));
+        self.request(command).map(|_| ())
+    }
+
+    pub fn set_default_source(&mut self, name: &str) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SET_DEFAULT_SOURCE);
+        command.string(Some(name));
+        self.request(command).map(|_| ())
+    }
+
+    /// Source named `name`, `@DEFAULT_SOURCE@` for the default one.
+    pub fn source(&mut self, name: &str) -> io::Result<Device> {
+        let mut command = self.command(COMMAND_GET_SOURCE_INFO);
+        command.u32(INVALID_INDEX).string(Some(name));
+        let mut reply = self.request(command)?;
+        reply.device()
+    }
+
+    pub fn set_source_volume(&mut self, name: &str, volume: &[u32]) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SET_SOURCE_VOLUME);
+        command
+            .u32(INVALID_INDEX)
+            .string(Some(name))
+            .cvolume(volume);
+        self.request(command).map(|_| ())
+    }
+
+    pub fn set_source_mute(&mut self, name: &str, muted: bool) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SET_SOURCE_MUTE);
+        command.u32(INVALID_INDEX).string(Some(name)).bool(muted);
+        self.request(command).map(|_| ())
+    }
+
+    /// Receive events for the facilities in `mask` through `next_event`.
+    pub fn subscribe(&mut self, mask: u32) -> io::Result<()> {
+        let mut command = self.command(COMMAND_SUBSCRIBE);
+        command.u32(mask);
+        self.request(command).map(|_| ())
+    }
+
+    /// Block until the server reports a change.
+    pub fn next_event(&mut self) -> io::Result<Event> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(event);
+        }
+        loop {
+            let mut reader = Reader::new(self.read_packet()?);
+            if reader.u32()? == COMMAND_SUBSCRIBE_EVENT {
+                reader.u32()?; // tag
+                return reader.event();
+            }
+        }
+    }
+
+    /// Entries of a sink or source list with the index of the device they
+    /// monitor. Both share their layout from protocol version 24 on.
+    fn devices(&mut self, list: u32) -> io::Result<Vec<(Device, u32)>> {
+        if self.version < 24 {
+            return Err(other("listing devices needs protocol version 24"));
+        }
+
+        let command = self.command(list);
+        let mut reply = self.request(command)?;
+        let mut devices = Vec::new();
+        while !reply.at_end() {
+            devices.push(reply.device_entry()?);
+        }
+        Ok(devices)
+    }
+
+    fn command(&mut self, command: u32) -> TagStruct {
+        let tag = self.next_tag;
+        self.next_tag = self.next_tag.wrapping_add(1);
+        TagStruct::new(command, tag)
+    }
+
+    /// Send `command` and wait for its reply, keeping events that arrive
+    /// in between for `next_event`.
+    fn request(&mut self, command: TagStruct) -> io::Result<Reader> {
+        let tag = command.tag;
+        self.write_packet(&command.data)?;
+
+        loop {
+            let mut reader = Reader::new(self.read_packet()?);
+            match reader.u32()? {
+                COMMAND_REPLY if reader.u32()? == tag => return Ok(reader),
+                COMMAND_ERROR if reader.u32()? == tag => {
+                    return Err(other(&format!("server error {}", reader.u32()?)));
+                }
+                COMMAND_SUBSCRIBE_EVENT => {
+                    reader.u32()?;
+                    let event = reader.event()?;
+                    self.pending.push_back(event);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn write_packet(&mut self, payload: &[u8]) -> io::Result<()> {
+        let mut packet = Vec::with_capacity(20 + payload.len());
+        packet.extend((payload.len() as u32).to_be_bytes());
+        packet.extend(CONTROL_CHANNEL.to_be_bytes());
+        packet.extend([0; 12]); // offset and flags
+        packet.extend(payload);
+        self.stream.write_all(&packet)
+    }
+
+    fn read_packet(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            let mut descriptor = [0; 20];
+            self.stream.read_exact(&mut descriptor)?;
+            let length = u32::from_be_bytes(descriptor[0..4].try_into().unwrap()) as usize;
+            let channel = u32::from_be_bytes(descriptor[4..8].try_into().unwrap());
+
+            let mut payload = vec![0; length];
+            self.stream.read_exact(&mut payload)?;
+            // Audio data of streams, we never create any
+            if channel == CONTROL_CHANNEL {
+                return Ok(payload);
+            }
+        }
+    }
+}
+
+fn other(message: &str) -> io::Error {
+    io::Error::other(message.to_string())
+}
+
+fn socket_path() -> Option<PathBuf> {
+    if let Ok(server) = std::env::var("PULSE_SERVER")
+        && let Some(path) = server.strip_prefix("unix:")
+    {
+        return Some(PathBuf::from(path));
+    }
+    if let Ok(runtime) = std::env::var("PULSE_RUNTIME_PATH") {
+        return Some(PathBuf::from(runtime).join("native"));
+    }
+    let runtime = std::env::var("XDG_RUNTIME_DIR").ok()?;
+    Some(PathBuf::from(runtime).join("pulse/native"))
+}
+
+/// Auth cookie of the user. PipeWire and same-user connections to
+/// PulseAudio don't check it, so a missing cookie is sent as zeros.
+fn cookie() -> Vec<u8> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    let candidates = [
+        std::env::var("PULSE_COOKIE").ok(),
+        Some(format!("{}/.config/pulse/cookie", home)),
+        Some(format!("{}/.pulse-cookie", home)),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .filter_map(|path| std::fs::read(path).ok())
+        .find(|cookie| cookie.len() == COOKIE_LENGTH)
+        .unwrap_or_else(|| vec![0; COOKIE_LENGTH])
+}
+
+/// Outgoing tagged values of a command.
+struct TagStruct {
+    tag: u32,
+    data: Vec<u8>,
+}
+
+impl TagStruct {
+    fn new(command: u32, tag: u32) -> Self {
+        let mut tag_struct = Self {
+            tag,
+            data: Vec::new(),
+        };
+        tag_struct.u32(command).u32(tag);
+        tag_struct
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.data.push(b'L');
+        self.data.extend(value.to_be_bytes());
+        self
+    }
+
+    fn bool(&mut self, value: bool) -> &mut Self {
+        self.data.push(if value { b'1' } else { b'0' });
+        self
+    }
+
+    fn string(&mut self, value: Option<&str>) -> &mut Self {
+        match value {
+            Some(value) => {
+                self.data.push(b't');
+                self.data.extend(value.as_bytes());
+                self.data.push(0);
+            }
+            None => self.data.push(b'N'),
+        }
+        self
+    }
+
+    fn arbitrary(&mut self, value: &[u8]) -> &mut Self {
+        self.data.push(b'x');
+        self.data.extend((value.len() as u32).to_be_bytes());
+        self.data.extend(value);
+        self
+    }
+
+    fn cvolume(&mut self, volume: &[u32]) -> &mut Self {
+        self.data.push(b'v');
+        self.data.push(volume.len() as u8);
+        for channel in volume {
+            self.data.extend(channel.to_be_bytes());
+        }
+        self
+    }
+
+    fn proplist(&mut self, properties: &[(&str, &str)]) -> &mut Self {
+        self.data.push(b'P');
+        for (key, value) in properties {
+            let mut value = value.as_bytes().to_vec();
+            value.push(0);
+            self.string(Some(key))
+                .u32(value.len() as u32)
+                .arbitrary(&value);
+        }
+        self.string(None)
+    }
+}
+
+/// Incoming tagged values of a reply or event.
+struct Reader {
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl Reader {
+    fn new(data: Vec<u8>) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&[u8]> {
+        let start = self.position;
+        if self.data.len() - start < length {
+            return Err(other("truncated packet"));
+        }
+        self.position += length;
+        Ok(&self.data[start..self.position])
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn expect(&mut self, tag: u8) -> io::Result<()> {
+        let found = self.take(1)?[0];
+        if found != tag {
+            return Err(other(&format!(
+                "expected tag '{}', found '{}'",
+                tag as char, found as char
+            )));
+        }
+        Ok(())
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.expect(b'L')?;
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn bool(&mut self) -> io::Result<bool> {
+        match self.take(1)?[0] {
+            b'1' => Ok(true),
+            b'0' => Ok(false),
+            found => Err(other(&format!(
+                "expected boolean, found '{}'",
+                found as char
+            ))),
+        }
+    }
+
+    fn string(&mut self) -> io::Result<Option<String>> {
+        match self.take(1)?[0] {
+            b'N' => Ok(None),
+            b't' => {
+                let end = self.data[self.position..]
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .ok_or_else(|| other("unterminated string"))?;
+                let value = String::from_utf8_lossy(self.take(end)?).into_owned();
+                self.take(1)?;
+                Ok(Some(value))
+            }
+            found => Err(other(&format!(
+                "expected string, found '{}'",
+                found as char
+            ))),
+        }
+    }
+
+    fn arbitrary(&mut self) -> io::Result<&[u8]> {
+        self.expect(b'x')?;
+        let length = u32::from_be_bytes(self.take(4)?.try_into().unwrap());
+        self.take(length as usize)
+    }
+
+    fn proplist(&mut self) -> io::Result<HashMap<String, String>> {
+        self.expect(b'P')?;
+        let mut properties = HashMap::new();
+        while let Some(key) = self.string()? {
+            self.u32()?; // length
+            // String values carry their terminating zero
+            let value = String::from_utf8_lossy(self.arbitrary()?);
+            properties.insert(key, value.trim_end_matches('\0').to_string());
+        }
+        Ok(properties)
+    }
+
+    fn sample_spec(&mut self) -> io::Result<()> {
+        self.expect(b'a')?;
+        self.take(6)?; // format, channels, rate
+        Ok(())
+    }
+
+    fn channel_map(&mut self) -> io::Result<()> {
+        self.expect(b'm')?;
+        let channels = self.take(1)?[0] as usize;
+        self.take(channels)?;
+        Ok(())
+    }
+
+    fn cvolume(&mut self) -> io::Result<Vec<u32>> {
+        self.expect(b'v')?;
+        let channels = self.take(1)?[0] as usize;
+        (0..channels)
+            .map(|_| Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap())))
+            .collect()
+    }
+
+    fn event(&mut self) -> io::Result<Event> {
+        let kind = self.u32()?;
+        self.u32()?; // index
+        let facility = match kind & 0x0F {
+            0 => Facility::Sink,
+            1 => Facility::Source,
+            2 => Facility::SinkInput,
+            7 => Facility::Server,
+            _ => Facility::Other,
+        };
+        Ok(Event { facility })
+    }
+
+    /// Skip one value of any type.
+    fn skip(&mut self) -> io::Result<()> {
+        match self.take(1)?[0] {
+            b'N' | b'1' | b'0' => {}
+            b'B' => {
+                self.take(1)?;
+            }
+            b'L' | b'V' => {
+                self.take(4)?;
+            }
+            b'R' | b'r' | b'U' | b'T' => {
+                self.take(8)?;
+            }
+            b'a' => {
+                self.take(6)?;
+            }
+            b'm' => {
+                let channels = self.take(1)?[0] as usize;
+                self.take(channels)?;
+            }
+            b'v' => {
+                let channels = self.take(1)?[0] as usize;
+                self.take(channels * 4)?;
+            }
+            b't' => {
+                self.position -= 1;
+                self.string()?;
+            }
+            b'x' => {
+                self.position -= 1;
+                self.arbitrary()?;
+            }
+            b'P' => {
+                self.position -= 1;
+                self.proplist()?;
+            }
+            b'f' => {
+                // Encoding and properties
+                self.skip()?;
+                self.skip()?;
+            }
+            found => {
+                return Err(other(&format!("unknown tag '{}'", found as char)));
+            }
+        }
+        Ok(())
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        self.expect(b'B')?;
+        Ok(self.take(1)?[0])
+    }
+
+    /// The leading fields shared by sink and source info.
+    fn device(&mut self) -> io::Result<Device> {
+        self.u32()?; // index
+        let name = self.string()?.unwrap_or_default();
+        let description = self.string()?.unwrap_or_default();
+        self.sample_spec()?;
+        self.channel_map()?;
+        self.u32()?; // owner module
+        let volume = self.cvolume()?;
+        let muted = self.bool()?;
+        Ok(Device {
+            name,
+            description,
+            volume,
+            muted,
+        })
+    }
+
+    fn sink_input(&mut self) -> io::Result<SinkInput> {
+        let index = self.u32()?;
+        let name = self.string()?.unwrap_or_default();
+        self.u32()?; // owner module
+        self.u32()?; // client
+        self.u32()?; // sink
+        self.sample_spec()?;
+        self.channel_map()?;
+        let volume = self.cvolume()?;
+        // Buffer latency, sink latency, resample method and driver
+        for _ in 0..4 {
+            self.skip()?;
+        }
+        let muted = self.bool()?;
+        let properties = self.proplist()?;
+        self.bool()?; // corked
+        let has_volume = self.bool()?;
+        self.bool()?; // volume writable
+        self.skip()?; // format
+
+        Ok(SinkInput {
+            index,
+            name,
+            properties,
+            volume,
+            muted,
+            has_volume,
+        })
+    }
+
+    /// A whole sink or source info, with the monitor source of a sink or
+    /// the sink monitored by a source.
+    fn device_entry(&mut self) -> io::Result<(Device, u32)> {
+        let device = self.device()?;
+        let monitor = self.u32()?;
+
+        // Monitor name, latency, driver, flags, properties, configured
+        // latency, base volume, state, volume steps and card
+        for _ in 0..10 {
+            self.skip()?;
+        }
+
+        let ports = self.u32()?;
+        // Name, description, priority and availability of every port
+        for _ in 0..ports * 4 {
+            self.skip()?;
+        }
+        self.string()?; // active port
+
+        let formats = self.u8()?;
+        for _ in 0..formats {
+            self.skip()?;
+        }
+
+        Ok((device, monitor))
+    }
+}
@@ -0,0 +1,775 @@
+// ============ modules/audio/widget.rs ============
+use super::pulse;
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
+use gtk4 as gtk;
+use gtk4::prelude::*;
+use once_cell::sync::Lazy;
+use std::io;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+
+pub struct AudioWidget {
+    container: gtk::Box,
+    label: gtk::Label,
+    audio_info: Arc<Mutex<AudioInfo>>,
+    tooltip: bool,
+    sources: SourceHandles,
+    /// Ends the native watcher thread and its connection.
+    stopper: pulse::Stopper,
+}
+
+/// Detected once, connecting blocks and the answer doesn't change.
+static BACKEND: Lazy<AudioBackend> = Lazy::new(detect_audio_backend);
+
+#[derive(Clone)]
+pub struct AudioConfig {
+    pub format: String,
+    pub icons: Vec<String>,
+    pub muted_icon: String,
+    pub interval: std::time::Duration,
+    pub tooltip: bool,
+    pub on_click: String,
+    pub on_click_right: String,
+    pub on_click_middle: String,
+    pub on_scroll_up: String,
+    pub on_scroll_down: String,
+    pub scroll_step: i32,
+    pub max_volume: i32,
+    pub device_popover: bool,
+    pub mixer_popover: bool,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            format: "{icon} {volume}%".to_string(),
+            icons: crate::config::AudioConfig::default_icons(),
+            muted_icon: crate::config::AudioConfig::default_muted_icon(),
+            interval: std::time::Duration::from_millis(250),
+            tooltip: true,
+            on_click: "".to_string(),
+            on_click_right: "".to_string(),
+            on_click_middle: "".to_string(),
+            on_scroll_up: "".to_string(),
+            on_scroll_down: "".to_string(),
+            scroll_step: 5,
+            max_volume: 100,
+            device_popover: false,
+            mixer_popover: false,
+        }
+    }
+}
+
+impl AudioConfig {
+    pub fn from_config(config: &crate::config::AudioConfig) -> Self {
+        Self {
+            format: config.format.clone(),
+            icons: config.icons.clone(),
+            muted_icon: config.muted_icon.clone(),
+            interval: config.interval,
+            tooltip: config.tooltip,
+            on_click: config.on_click.clone(),
+            on_click_right: config.on_click_right.clone(),
+            on_click_middle: config.on_click_middle.clone(),
+            on_scroll_up: config.scroll_up.clone(),
+            on_scroll_down: config.scroll_down.clone(),
+            scroll_step: config.scroll_step,
+            max_volume: config.max_volume,
+            device_popover: config.device_popover,
+            mixer_popover: config.mixer_popover,
+        }
+    }
+
+    pub fn from_microphone_config(config: &crate::config::MicrophoneConfig) -> Self {
+        Self {
+            format: config.format.clone(),
+            icons: config.icons.clone(),
+            muted_icon: config.muted_icon.clone(),
+            interval: config.interval,
+            tooltip: config.tooltip,
+            on_click: config.on_click.clone(),
+            on_click_right: config.on_click_right.clone(),
+            on_click_middle: config.on_click_middle.clone(),
+            on_scroll_up: config.scroll_up.clone(),
+            on_scroll_down: config.scroll_down.clone(),
+            scroll_step: config.scroll_step,
+            max_volume: config.max_volume,
+            device_popover: config.device_popover,
+            // Playback streams don't belong to an input
+            mixer_popover: false,
+        }
+    }
+}
+
+/// Which default device a widget follows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeviceKind {
+    /// Output, `audio`
+    Sink,
+    /// Input, `microphone`
+    Source,
+}
+
+impl DeviceKind {
+    /// Module name, used for CSS classes and log messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sink => "audio",
+            Self::Source => "microphone",
+        }
+    }
+
+    pub fn wpctl_target(self) -> &'static str {
+        match self {
+            Self::Sink => "@DEFAULT_AUDIO_SINK@",
+            Self::Source => "@DEFAULT_AUDIO_SOURCE@",
+        }
+    }
+
+    /// Object in pactl commands such as `get-sink-volume`.
+    pub fn pactl_object(self) -> &'static str {
+        match self {
+            Self::Sink => "sink",
+            Self::Source => "source",
+        }
+    }
+
+    /// Default device name for pactl and the native protocol.
+    pub fn default_name(self) -> &'static str {
+        match self {
+            Self::Sink => pulse::DEFAULT_SINK,
+            Self::Source => pulse::DEFAULT_SOURCE,
+        }
+    }
+
+    fn facility(self) -> pulse::Facility {
+        match self {
+            Self::Sink => pulse::Facility::Sink,
+            Self::Source => pulse::Facility::Source,
+        }
+    }
+
+    fn subscribe_mask(self) -> u32 {
+        match self {
+            Self::Sink => pulse::SUBSCRIBE_SINK,
+            Self::Source => pulse::SUBSCRIBE_SOURCE,
+        }
+    }
+
+    pub fn read(self, connection: &mut pulse::Connection, name: &str) -> io::Result<pulse::Device> {
+        match self {
+            Self::Sink => connection.sink(name),
+            Self::Source => connection.source(name),
+        }
+    }
+
+    pub fn set_volume(
+        self,
+        connection: &mut pulse::Connection,
+        name: &str,
+        volume: &[u32],
+    ) -> io::Result<()> {
+        match self {
+            Self::Sink => connection.set_sink_volume(name, volume),
+            Self::Source => connection.set_source_volume(name, volume),
+        }
+    }
+
+    fn set_mute(
+        self,
+        connection: &mut pulse::Connection,
+        name: &str,
+        muted: bool,
+    ) -> io::Result<()> {
+        match self {
+            Self::Sink => connection.set_sink_mute(name, muted),
+            Self::Source => connection.set_source_mute(name, muted),
+        }
+    }
+
+    pub fn set_default(self, connection: &mut pulse::Connection, name: &str) -> io::Result<()> {
+        match self {
+            Self::Sink => connection.set_default_sink(name),
+            Self::Source => connection.set_default_source(name),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct AudioInfo {
+    volume: i32,
+    muted: bool,
+    backend: AudioBackend,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AudioBackend {
+    /// PulseAudio native protocol, served by PulseAudio and pipewire-pulse
+    Native,
+    PipeWire,
+    PulseAudio,
+    Unknown,
+}
+
+impl Module for AudioWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        Some(Self::new(
+            AudioConfig::from_config(&ctx.instance_config(&ctx.config.audio)),
+            DeviceKind::Sink,
+        ))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.container.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+        self.stopper.stop();
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        // Volume classes are set on the label
+        if let Some(classes) = state["classes"].as_array_mut() {
+            classes.extend(
+                self.label
+                    .css_classes()
+                    .iter()
+                    .map(|class| serde_json::json!(class.as_str())),
+            );
+        }
+        state["tooltip"] = serde_json::json!(
+            self.tooltip
+                .then(|| tooltip_text(&self.audio_info.lock().unwrap()))
+        );
+        state
+    }
+}
+
+impl AudioWidget {
+    pub fn new(config: AudioConfig, kind: DeviceKind) -> Self {
+        let container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        container.add_css_class(kind.name());
+        container.add_css_class("module");
+
+        let button = gtk::Button::new();
+        button.add_css_class(&format!("{}-button", kind.name()));
+
+        let label = gtk::Label::new(Some(""));
+        label.add_css_class(&format!("{}-label", kind.name()));
+        button.set_child(Some(&label));
+
+        container.append(&button);
+
+        let audio_info = Arc::new(Mutex::new(AudioInfo {
+            volume: 0,
+            muted: false,
+            backend: AudioBackend::Unknown,
+        }));
+
+        // Detect backend and get initial info
+        let backend = BACKEND.clone();
+        let info = get_audio_info(&backend, kind);
+        *audio_info.lock().unwrap() = info.clone();
+        update_label(
+            &label,
+            &info,
+            config.format.clone(),
+            config.muted_icon.clone(),
+            config.icons.clone(),
+        );
+
+        // Set up click handler
+        let on_click = config.on_click.clone();
+        let backend_click = backend.clone();
+        button.connect_clicked(move |_| {
+            if !on_click.is_empty() {
+                crate::shared::run_shell_command(&on_click);
+            } else {
+                // Default on_click: toggle mute
+                toggle_mute(&backend_click, kind);
+            }
+        });
+
+        // Middle and right click handler
+        let right_click = config.on_click_right.clone();
+        let middle_click = config.on_click_middle.clone();
+        let device_popover = config.device_popover;
+        let mixer_popover = config.mixer_popover;
+        let max_volume = config.max_volume;
+        let backend_popover = backend.clone();
+        let gesture = gtk::GestureClick::new();
+        gesture.set_button(0); // Listen to all buttons
+        gesture.connect_released(move |gesture, _, _, _| {
+            let button_num = gesture.current_button();
+            match button_num {
+                2 => {
+                    // Middle Click
+                    // Falls back to the command where streams can't be listed
+                    if mixer_popover && super::supports_streams(&backend_popover) {
+                        if let Some(button) = gesture.widget().and_downcast::<gtk::Button>() {
+                            super::show_mixer_popover(&button, backend_popover.clone(), max_volume);
+                        }
+                    } else {
+                        crate::shared::run_shell_command(&middle_click);
+                    }
+                }
+                3 => {
+                    // Right Click
+                    if device_popover {
+                        if let Some(button) = gesture.widget().and_downcast::<gtk::Button>() {
+                            super::show_device_popover(
+                                &button,
+                                backend_popover.clone(),
+                                max_volume,
+                            );
+                        }
+                    } else {
+                        crate::shared::run_shell_command(&right_click);
+                    }
+                }
+                _ => {}
+            }
+        });
+
+        button.add_controller(gesture);
+
+        // Set up scroll handler
+        let scroll_controller =
+            gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+
+        let scroll_up = config.on_scroll_up.clone();
+        let scroll_down = config.on_scroll_down.clone();
+        let scroll_step = config.scroll_step;
+        let max_volume = config.max_volume;
+        let backend_scroll = backend.clone();
+        scroll_controller.connect_scroll(move |_, _, dy| {
+            if dy < 0.0 {
+                // Scroll up - increase volume
+                if !scroll_up.is_empty() {
+                    crate::shared::run_shell_command(&scroll_up);
+                } else {
+                    change_volume(&backend_scroll, kind, scroll_step, max_volume);
+                }
+            } else {
+                // Scroll down - decrease volume
+                if !scroll_down.is_empty() {
+                    crate::shared::run_shell_command(&scroll_down);
+                } else {
+                    change_volume(&backend_scroll, kind, -scroll_step, max_volume);
+                }
+            }
+            gtk4::glib::Propagation::Stop
+        });
+
+        button.add_controller(scroll_controller);
+
+        // Follow server events natively, poll the command line tools otherwise
+        let label_clone = label.clone();
+        let audio_info_clone = audio_info.clone();
+        let backend_clone = backend.clone();
+
+        let sources = SourceHandles::default();
+        let stopper = pulse::Stopper::default();
+        if backend == AudioBackend::Native {
+            // The main loop only wakes when the watcher sends a change, the
+            // loop ends with the watcher once the widget is torn down
+            let (sender, mut receiver) = unbounded_channel::<AudioInfo>();
+            watch_native(sender, kind, stopper.clone());
+
+            glib::spawn_future_local(async move {
+                while let Some(mut info) = receiver.recv().await {
+                    // Only the latest state matters after a burst of events
+                    while let Ok(newer) = receiver.try_recv() {
+                        info = newer;
+                    }
+                    *audio_info_clone.lock().unwrap() = info.clone();
+                    update_label(
+                        &label_clone,
+                        &info,
+                        config.format.clone(),
+                        config.muted_icon.clone(),
+                        config.icons.clone(),
+                    );
+                }
+            });
+        } else {
+            sources.push(glib::timeout_add_local(config.interval, move || {
+                let info = get_audio_info(&backend_clone, kind);
+                *audio_info_clone.lock().unwrap() = info.clone();
+                update_label(
+                    &label_clone,
+                    &info,
+                    config.format.clone(),
+                    config.muted_icon.clone(),
+                    config.icons.clone(),
+                );
+                glib::ControlFlow::Continue
+            }));
+        }
+
+        // Add tooltip if enabled
+        if config.tooltip {
+            let audio_info_clone = audio_info.clone();
+            container.set_has_tooltip(true);
+            container.connect_query_tooltip(move |_, _, _, _, tooltip| {
+                tooltip.set_text(Some(&tooltip_text(&audio_info_clone.lock().unwrap())));
+                true
+            });
+        }
+
+        Self {
+            container,
+            label,
+            audio_info,
+            tooltip: config.tooltip,
+            sources,
+            stopper,
+        }
+    }
+}
+
+fn tooltip_text(info: &AudioInfo) -> String {
+    format!(
+        "Volume: {}%\nStatus: {}\nBackend: {:?}",
+        info.volume,
+        if info.muted { "Muted" } else { "Active" },
+        info.backend
+    )
+}
+
+fn update_label(
+    label: &gtk::Label,
+    info: &AudioInfo,
+    format: String,
+    muted_icon: String,
+    icons: Vec<String>,
+) {
+    let icon = get_icon_for_volume(info.volume, info.muted, muted_icon, icons);
+
+    let text = format
+        .replace("{icon}", &icon)
+        .replace("{volume}", &info.volume.to_string());
+
+    label.set_text(&text);
+
+    // Update CSS classes
+    label.remove_css_class("muted");
+    label.remove_css_class("low");
+    label.remove_css_class("medium");
+    label.remove_css_class("high");
+    label.remove_css_class("overamplified");
+
+    if info.muted {
+        label.add_css_class("muted");
+    } else if info.volume <= 33 {
+        label.add_css_class("low");
+    } else if info.volume <= 66 {
+        label.add_css_class("medium");
+    } else {
+        label.add_css_class("high");
+    }
+    if !info.muted && info.volume > 100 {
+        label.add_css_class("overamplified");
+    }
+}
+
+fn get_icon_for_volume(volume: i32, muted: bool, muted_icon: String, icons: Vec<String>) -> String {
+    if muted {
+        return muted_icon;
+    }
+
+    let n = icons.len();
+    let idx = if volume <= 0 {
+        0
+    } else if volume >= 100 {
+        n - 1
+    } else {
+        ((volume as f32 / 100.0) * (n as f32 - 1.0)).round() as usize
+    };
+
+    icons[idx].clone()
+}
+
+fn detect_audio_backend() -> AudioBackend {
+    // Talk to the sound server directly if it is reachable
+    if pulse::Connection::connect().is_ok() {
+        return AudioBackend::Native;
+    }
+
+    // Check for wpctl (PipeWire/WirePlumber)
+    if Command::new("wpctl").arg("--version").output().is_ok() {
+        return AudioBackend::PipeWire;
+    }
+
+    // Check for pactl (PulseAudio)
+    if Command::new("pactl").arg("--version").output().is_ok() {
+        return AudioBackend::PulseAudio;
+    }
+
+    AudioBackend::Unknown
+}
+
+fn get_audio_info(backend: &AudioBackend, kind: DeviceKind) -> AudioInfo {
+    match backend {
+        AudioBackend::Native => get_native_info(kind).unwrap_or(AudioInfo {
+            volume: 0,
+            muted: false,
+            backend: AudioBackend::Native,
+        }),
+        AudioBackend::PipeWire => get_pipewire_info(kind),
+        AudioBackend::PulseAudio => get_pulseaudio_info(kind),
+        AudioBackend::Unknown => AudioInfo {
+            volume: 0,
+            muted: false,
+            backend: AudioBackend::Unknown,
+        },
+    }
+}
+
+fn get_native_info(kind: DeviceKind) -> io::Result<AudioInfo> {
+    read_native_info(&mut pulse::Connection::connect()?, kind)
+}
+
+fn read_native_info(connection: &mut pulse::Connection, kind: DeviceKind) -> io::Result<AudioInfo> {
+    let device = kind.read(connection, kind.default_name())?;
+    Ok(AudioInfo {
+        volume: device.percent(),
+        muted: device.muted,
+        backend: AudioBackend::Native,
+    })
+}
+
+/// Follow the default device through server events and send its state
+/// whenever it changes. Reconnects when the sound server restarts, until
+/// `stopper` is stopped.
+fn watch_native(sender: UnboundedSender<AudioInfo>, kind: DeviceKind, stopper: pulse::Stopper) {
+    std::thread::spawn(move || {
+        let mut last = None;
+        while !stopper.is_stopped() {
+            match follow_default_device(&sender, kind, &mut last, &stopper) {
+                // The widget is gone
+                Ok(()) => return,
+                Err(_) if stopper.is_stopped() => return,
+                Err(e) => {
+                    eprintln!(
+                        "[{}] lost connection to the sound server: {}",
+                        kind.name(),
+                        e
+                    );
+                    std::thread::sleep(Duration::from_secs(2));
+                }
+            }
+        }
+    });
+}
+
+fn follow_default_device(
+    sender: &UnboundedSender<AudioInfo>,
+    kind: DeviceKind,
+    last: &mut Option<AudioInfo>,
+    stopper: &pulse::Stopper,
+) -> io::Result<()> {
+    let mut connection = pulse::Connection::connect()?;
+    stopper.watch(&connection)?;
+    // Server events cover a change of the default device
+    connection.subscribe(kind.subscribe_mask() | pulse::SUBSCRIBE_SERVER)?;
+
+    loop {
+        let info = read_native_info(&mut connection, kind)?;
+        if last.as_ref() != Some(&info) {
+            if sender.send(info.clone()).is_err() {
+                return Ok(());
+            }
+            *last = Some(info);
+        }
+        // Wait for a change of a device or of the default one
+        loop {
+            let facility = connection.next_event()?.facility;
+            if facility == kind.facility() || facility == pulse::Facility::Server {
+                break;
+            }
+        }
+    }
+}
+
+fn get_pipewire_info(kind: DeviceKind) -> AudioInfo {
+    // Get default device ID
+    let sink_output = Command::new("wpctl")
+        .args(["get-volume", kind.wpctl_target()])
+        .output();
+
+    if let Ok(output) = sink_output {
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        // Output format: "Volume: 0.50" or "Volume: 0.50 [MUTED]"
+
+        let muted = output_str.contains("[MUTED]");
+
+        // Parse volume (0.0 to 1.0)
+        let volume = output_str
+            .split_whitespace()
+            .nth(1)
+            .and_then(|s| s.parse::<f32>().ok())
+            .map(|v| (v * 100.0).round() as i32)
+            .unwrap_or(0);
+
+        return AudioInfo {
+            volume,
+            muted,
+            backend: AudioBackend::PipeWire,
+        };
+    }
+
+    AudioInfo {
+        volume: 0,
+        muted: false,
+        backend: AudioBackend::PipeWire,
+    }
+}
+
+fn get_pulseaudio_info(kind: DeviceKind) -> AudioInfo {
+    let output = Command::new("pactl")
+        .args([
+            &format!("get-{}-volume", kind.pactl_object()),
+            kind.default_name(),
+        ])
+        .output();
+
+    let mute_output = Command::new("pactl")
+        .args([
+            &format!("get-{}-mute", kind.pactl_object()),
+            kind.default_name(),
+        ])
+        .output();
+
+    let mut volume = 0;
+    let mut muted = false;
+
+    if let Ok(output) = output {
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        // Parse volume percentage from output like: "Volume: front-left: 65536 / 100% ..."
+        for part in output_str.split_whitespace() {
+            if part.ends_with('%')
+                && let Ok(vol) = part.trim_end_matches('%').parse::<i32>()
+            {
+                volume = vol;
+                break;
+            }
+        }
+    }
+
+    if let Ok(output) = mute_output {
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        muted = output_str.contains("yes");
+    }
+
+    AudioInfo {
+        volume,
+        muted,
+        backend: AudioBackend::PulseAudio,
+    }
+}
+
+fn toggle_mute(backend: &AudioBackend, kind: DeviceKind) {
+    std::thread::spawn({
+        let backend = backend.clone();
+        move || match backend {
+            AudioBackend::Native => {
+                let result = pulse::Connection::connect().and_then(|mut connection| {
+                    let device = kind.read(&mut connection, kind.default_name())?;
+                    kind.set_mute(&mut connection, kind.default_name(), !device.muted)
+                });
+                if let Err(e) = result {
+                    eprintln!("[{}] failed to toggle mute: {}", kind.name(), e);
+                }
+            }
+            AudioBackend::PipeWire => {
+                let _ = Command::new("wpctl")
+                    .args(["set-mute", kind.wpctl_target(), "toggle"])
+                    .output();
+            }
+            AudioBackend::PulseAudio => {
+                let _ = Command::new("pactl")
+                    .args([
+                        &format!("set-{}-mute", kind.pactl_object()),
+                        kind.default_name(),
+                        "toggle",
+                    ])
+                    .output();
+            }
+            AudioBackend::Unknown => {}
+        }
+    });
+}
+
+/// Raise or lower the volume by `delta` percent, never above `max_volume`.
+fn change_volume(backend: &AudioBackend, kind: DeviceKind, delta: i32, max_volume: i32) {
+    std::thread::spawn({
+        let backend = backend.clone();
+        move || match backend {
+            AudioBackend::Native => {
+                let result = pulse::Connection::connect().and_then(|mut connection| {
+                    let device = kind.read(&mut connection, kind.default_name())?;
+                    // Shift every channel to keep the balance
+                    let step = pulse::from_percent(delta.abs());
+                    let max = pulse::from_percent(max_volume);
+                    let volume: Vec<u32> = device
+                        .volume
+                        .iter()
+                        .map(|channel| {
+                            if delta > 0 {
+                                channel.saturating_add(step).min(max)
+                            } else {
+                                channel.saturating_sub(step).min(max)
+                            }
+                        })
+                        .collect();
+                    kind.set_volume(&mut connection, kind.default_name(), &volume)
+                });
+                if let Err(e) = result {
+                    eprintln!("[{}] failed to change volume: {}", kind.name(), e);
+                }
+            }
+            AudioBackend::PipeWire => {
+                let change = if delta > 0 {
+                    format!("{}%+", delta)
+                } else {
+                    format!("{}%-", -delta)
+                };
+                let limit = format!("{:.2}", max_volume as f64 / 100.0);
+                let _ = Command::new("wpctl")
+                    .args([
+                        "set-volume",
+                        "--limit",
+                        &limit,
+                        kind.wpctl_target(),
+                        &change,
+                    ])
+                    .output();
+            }
+            AudioBackend::PulseAudio => {
+                // pactl has no limit, steps that would cross it land on it
+                let volume = get_pulseaudio_info(kind).volume;
+                let change = if volume + delta > max_volume {
+                    format!("{}%", max_volume)
+                } else if delta > 0 {
+                    format!("+{}%", delta)
+                } else {
+                    format!("{}%", delta)
+                };
+                let _ = Command::new("pactl")
+                    .args([
+                        &format!("set-{}-volume", kind.pactl_object()),
+                        kind.default_name(),
+                        &change,
+                    ])
+                    .output();
+            }
+            AudioBackend::Unknown => {}
+        }
+    });
+}
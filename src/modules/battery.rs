@@ -1,4 +1,6 @@
 // ============ modules/battery.rs ============
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 use gtk4 as gtk;
 use gtk4::prelude::*;
 use std::fs;
@@ -9,6 +11,9 @@ use crate::shared::{Gestures, create_gesture_handler};
 
 pub struct BatteryWidget {
     button: gtk::Button,
+    battery_info: Arc<Mutex<BatteryInfo>>,
+    tooltip: bool,
+    sources: SourceHandles,
 }
 
 #[derive(Clone)]
@@ -17,7 +22,7 @@ pub struct BatteryConfig {
     pub icons: Vec<String>,
     pub charging_icon: String,
     pub not_charging_icon: String,
-    pub interval: u64,
+    pub interval: std::time::Duration,
     pub battery: Option<String>,
     pub tooltip: bool,
     pub gestures: Gestures,
@@ -30,7 +35,7 @@ impl Default for BatteryConfig {
             icons: crate::config::BatteryConfig::default_icons(),
             charging_icon: crate::config::BatteryConfig::charging_icon(),
             not_charging_icon: crate::config::BatteryConfig::not_charging_icon(),
-            interval: 30,
+            interval: std::time::Duration::from_secs(30),
             battery: None,
             tooltip: true,
             gestures: Gestures {
@@ -73,6 +78,31 @@ struct BatteryInfo {
     power_now: f64,
 }
 
+impl Module for BatteryWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        Some(Self::new(Arc::new(BatteryConfig::from_config(
+            &ctx.instance_config(&ctx.config.battery),
+        ))))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.button.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        let info = self.battery_info.lock().unwrap();
+        state["capacity"] = serde_json::json!(info.capacity);
+        state["status"] = serde_json::json!(info.status);
+        state["tooltip"] = serde_json::json!(self.tooltip.then(|| tooltip_text(&info)));
+        state
+    }
+}
+
 impl BatteryWidget {
     pub fn new(config: Arc<BatteryConfig>) -> Self {
         let button = gtk::Button::with_label("");
@@ -100,41 +130,52 @@ impl BatteryWidget {
         let config_clone = Arc::clone(&config);
         let battery_info_clone = battery_info.clone();
 
-        glib::timeout_add_seconds_local(config.interval as u32, move || {
+        let sources = SourceHandles::default();
+        sources.push(glib::timeout_add_local(config.interval, move || {
             let info = get_battery_info(config_clone.battery.as_deref());
-            *battery_info_clone.lock().unwrap() = info.clone();
+            let previous =
+                std::mem::replace(&mut *battery_info_clone.lock().unwrap(), info.clone());
+            if previous.capacity != info.capacity || previous.status != info.status {
+                crate::ipc::emit(
+                    "battery",
+                    serde_json::json!({ "capacity": info.capacity, "status": info.status }),
+                );
+            }
             update_button(&button_clone, &info, &config_clone);
             glib::ControlFlow::Continue
-        });
+        }));
 
         // Add tooltip if enabled
         if config.tooltip {
             let battery_info_clone = battery_info.clone();
             button.set_has_tooltip(true);
             button.connect_query_tooltip(move |_, _, _, _, tooltip| {
-                let info = battery_info_clone.lock().unwrap();
-                let tooltip_text = format!(
-                    "Status: {}\nCapacity: {}%\n{}Power: {:.2}W",
-                    info.status,
-                    info.capacity,
-                    if !info.time_remaining.is_empty() {
-                        format!("{}\n", info.time_remaining)
-                    } else {
-                        String::new()
-                    },
-                    info.power_now
-                );
-                tooltip.set_text(Some(&tooltip_text));
+                tooltip.set_text(Some(&tooltip_text(&battery_info_clone.lock().unwrap())));
                 true
             });
         }
 
-        Self { button }
+        Self {
+            button,
+            battery_info,
+            tooltip: config.tooltip,
+            sources,
+        }
     }
+}
 
-    pub fn widget(&self) -> &gtk::Button {
-        &self.button
-    }
+fn tooltip_text(info: &BatteryInfo) -> String {
+    format!(
+        "Status: {}\nCapacity: {}%\n{}Power: {:.2}W",
+        info.status,
+        info.capacity,
+        if !info.time_remaining.is_empty() {
+            format!("{}\n", info.time_remaining)
+        } else {
+            String::new()
+        },
+        info.power_now
+    )
 }
 
 fn update_button(button: &gtk::Button, info: &BatteryInfo, config: &BatteryConfig) {
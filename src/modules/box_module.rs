@@ -1,4 +1,5 @@
 // ============ modules/box_widget.rs ============
+use crate::modules::{Module, ModuleContext, NamedModule};
 use crate::shared::{Gestures, create_gesture_handler};
 use gtk4 as gtk;
 use gtk4::prelude::*;
@@ -6,6 +7,7 @@ use gtk4::prelude::*;
 
 pub struct BoxWidget {
     container: gtk::Box,
+    modules: Vec<NamedModule>,
 }
 
 #[derive(Clone)]
@@ -16,6 +18,53 @@ pub struct BoxWidgetConfig {
     pub orientation: String,
 }
 
+impl BoxWidgetConfig {
+    pub fn from_config(config: &crate::config::BoxConfig) -> Self {
+        Self {
+            modules: config.modules.clone(),
+            gestures: Gestures {
+                on_click: config.on_click.clone(),
+                on_click_middle: config.on_click_middle.clone(),
+                on_click_right: config.on_click_right.clone(),
+                scroll_up: config.scroll_up.clone(),
+                scroll_down: config.scroll_down.clone(),
+            },
+            spacing: config.spacing,
+            orientation: config
+                .orientation
+                .clone()
+                .unwrap_or_else(|| "horizontal".to_string()),
+        }
+    }
+}
+
+impl Module for BoxWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        let box_config = &ctx.instance_config(ctx.config.boxes.get(ctx.key)?);
+        Some(Self::new(
+            ctx.key,
+            BoxWidgetConfig::from_config(box_config),
+            ctx.config,
+        ))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.container.clone().upcast()
+    }
+
+    fn update(&self) {
+        self.modules.iter().for_each(|module| module.update());
+    }
+
+    fn children(&self) -> &[NamedModule] {
+        &self.modules
+    }
+
+    fn teardown(&self) {
+        self.modules.iter().for_each(|module| module.teardown());
+    }
+}
+
 impl BoxWidget {
     pub fn new(name: &str, config: BoxWidgetConfig, app_config: &crate::config::Config) -> Self {
         // Determine orientation
@@ -32,12 +81,8 @@ impl BoxWidget {
         create_gesture_handler(&container, config.gestures);
 
         // Build the modules inside this box
-        crate::build_modules(&container, &config.modules, app_config, 1);
-
-        Self { container }
-    }
+        let modules = crate::modules::build_modules(&container, &config.modules, app_config, 1);
 
-    pub fn widget(&self) -> &gtk::Box {
-        &self.container
+        Self { container, modules }
     }
 }
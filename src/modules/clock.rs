@@ -1,34 +1,42 @@
 // ============ modules/clock.rs ============
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 use gtk4 as gtk;
 use gtk4::prelude::*;
 use libc::{localtime_r, strftime, time};
 use std::ffi::{CStr, CString};
+use std::rc::Rc;
 
 pub struct ClockWidget {
     pub button: gtk::Button,
+    /// Text of the tooltip, if enabled, shared with the IPC state.
+    tooltip: Option<Rc<dyn Fn() -> String>>,
+    sources: SourceHandles,
 }
 
 #[derive(Clone)]
 pub struct ClockConfig {
     pub format: String,
-    pub interval: u64,
+    pub interval: std::time::Duration,
     pub tooltip: bool,
     pub tooltip_format: String,
     pub on_click: String,
     pub on_click_middle: String,
     pub on_click_right: String,
+    pub timezone: Option<String>,
 }
 
 impl Default for ClockConfig {
     fn default() -> Self {
         Self {
             format: "%H:%M".to_string(),
-            interval: 1,
+            interval: std::time::Duration::from_millis(1),
             tooltip: true,
             tooltip_format: "%A, %B %d, %Y".to_string(),
             on_click: String::new(),
             on_click_middle: String::new(),
             on_click_right: String::new(),
+            timezone: None,
         }
     }
 }
@@ -43,10 +51,33 @@ impl ClockConfig {
             on_click: config.on_click.clone(),
             on_click_middle: config.on_click_middle.clone(),
             on_click_right: config.on_click_right.clone(),
+            timezone: config.timezone.clone(),
         }
     }
 }
 
+impl Module for ClockWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        Some(Self::new(ClockConfig::from_config(
+            &ctx.instance_config(&ctx.config.clock),
+        )))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.button.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        state["tooltip"] = serde_json::json!(self.tooltip.as_ref().map(|text| text()));
+        state
+    }
+}
+
 impl ClockWidget {
     pub fn new(config: ClockConfig) -> Self {
         let button = gtk::Button::with_label("--:--");
@@ -65,42 +96,63 @@ impl ClockWidget {
             },
         );
 
+        // Unknown identifiers resolve to UTC, no timezone means local time
+        let timezone = config
+            .timezone
+            .as_deref()
+            .map(|identifier| glib::TimeZone::new(Some(identifier)));
+
         // Set up tooltip if enabled
-        if config.tooltip {
+        let tooltip = config.tooltip.then(|| {
             let tooltip_format = config.tooltip_format.clone();
+            let timezone = timezone.clone();
+            let text: Rc<dyn Fn() -> String> =
+                Rc::new(move || format_time(tooltip_format.as_str(), timezone.as_ref()));
+
             button.set_has_tooltip(true);
-            button.connect_query_tooltip(move |_, _, _, _, tooltip| {
-                let tooltip_text = format_local_time(tooltip_format.as_str());
-                tooltip.set_text(Some(&tooltip_text));
-                true
+            button.connect_query_tooltip({
+                let text = text.clone();
+                move |_, _, _, _, tooltip| {
+                    tooltip.set_text(Some(&text()));
+                    true
+                }
             });
-        }
+            text
+        });
 
         // Set initial label
-        button.set_label(&format_local_time(&config.format));
+        button.set_label(&format_time(&config.format, timezone.as_ref()));
 
         // Clone button for the closure
         let button_clone = button.clone();
 
         // Poll for update
+        let sources = SourceHandles::default();
         let mut last_label = String::new();
-        glib::timeout_add_local(
-            std::time::Duration::from_millis(config.interval),
-            move || {
-                let current_label = &format_local_time(&config.format);
-                if last_label.as_str() != current_label {
-                    last_label = current_label.clone();
-                    button_clone.set_label(current_label);
-                }
-                glib::ControlFlow::Continue
-            },
-        );
+        sources.push(glib::timeout_add_local(config.interval, move || {
+            let current_label = &format_time(&config.format, timezone.as_ref());
+            if last_label.as_str() != current_label {
+                last_label = current_label.clone();
+                button_clone.set_label(current_label);
+            }
+            glib::ControlFlow::Continue
+        }));
 
-        Self { button }
+        Self {
+            button,
+            tooltip,
+            sources,
+        }
     }
+}
 
-    pub fn widget(&self) -> &gtk::Button {
-        &self.button
+fn format_time(fmt: &str, timezone: Option<&glib::TimeZone>) -> String {
+    match timezone {
+        Some(timezone) => glib::DateTime::now(timezone)
+            .and_then(|now| now.format(fmt))
+            .map(|text| text.to_string())
+            .unwrap_or_default(),
+        None => format_local_time(fmt),
     }
 }
 
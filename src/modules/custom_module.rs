@@ -1,14 +1,81 @@
 // ============ custom_module.rs ============
+use crate::config::FormatIcons;
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 use gtk4 as gtk;
+use gtk4::gio;
 use gtk4::prelude::*;
+use serde::Deserialize;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
 use std::process::{Command, Stdio};
+use std::rc::Rc;
 use std::sync::mpsc;
 use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long the output of `tooltip_exec` is shown before it is run again.
+const TOOLTIP_MAX_AGE: Duration = Duration::from_secs(1);
+
+thread_local! {
+    // Built custom modules by module name, e.g. `custom/vpn`, for IPC
+    static CUSTOM_MODULES: RefCell<HashMap<String, Vec<CustomHandle>>> =
+        RefCell::new(HashMap::new());
+}
 
 pub struct CustomModuleWidget {
+    name: String,
+    button: gtk::Button,
+    handle: CustomHandle,
+    /// Text of a tooltip filled on query, see `connect_tooltip`.
+    tooltip: Option<Rc<dyn Fn() -> Option<String>>>,
+    sources: SourceHandles,
+}
+
+/// What IPC needs to reach a built custom module.
+#[derive(Clone)]
+struct CustomHandle {
+    output: Output,
+    /// Wakes the polling thread, `None` when there is nothing to re-run.
+    refresh: Option<mpsc::Sender<()>>,
+}
+
+/// Label, tooltip and classes of a custom module, set from its output.
+#[derive(Clone)]
+struct Output {
     button: gtk::Button,
     label: gtk::Label,
+    format: Option<String>,
+    format_icons: Option<FormatIcons>,
+    /// Parse every output as `JsonOutput` instead of plain text.
+    json: bool,
+    /// Take the tooltip from JSON output, unless the config sets it.
+    json_tooltip: bool,
+    hide_empty: bool,
+    /// Classes added by the last output, removed again by the next one.
+    classes: Rc<RefCell<Vec<String>>>,
+}
+
+/// Output in the Waybar custom module protocol:
+/// `{"text", "alt", "tooltip", "class", "percentage"}`.
+#[derive(Deserialize)]
+struct JsonOutput {
+    text: Option<String>,
+    alt: Option<String>,
+    tooltip: Option<String>,
+    #[serde(default)]
+    class: Classes,
+    percentage: Option<f64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(untagged)]
+enum Classes {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
 }
 
 pub struct CustomModuleConfig<'a> {
@@ -19,14 +86,310 @@ pub struct CustomModuleConfig<'a> {
     pub scroll_up: String,
     pub scroll_down: String,
     pub exec: String,
-    pub interval: u64,
+    /// Skip `exec` and hide the module while this command fails.
+    pub exec_if: Option<String>,
+    pub hide_empty: bool,
+    pub interval: Duration,
+    /// Run `exec` on wall-clock multiples of this instead of every `interval`.
+    pub schedule: Option<Duration>,
+    /// Re-run `exec` on `SIGRTMIN+signal`, see `shared::signals`.
+    pub signal: Option<u32>,
     pub format: Option<String>,
+    pub format_icons: Option<FormatIcons>,
+    /// Every output is a JSON object, see `JsonOutput`.
+    pub json: bool,
+    pub tooltip: Option<bool>,
+    pub tooltip_exec: Option<String>,
+    pub tooltip_format: Option<String>,
     /// When true, `exec` is run once and kept alive; every line it writes to
     /// stdout immediately becomes the new label.  The process is restarted
     /// automatically if it exits.  `interval` is ignored in this mode.
     pub listen: bool,
 }
 
+impl Module for CustomModuleWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        let custom_config = &ctx.instance_config(ctx.config.custom_modules.get(ctx.key)?);
+        let mut widget = Self::new(CustomModuleConfig {
+            name: ctx.key,
+            on_click: custom_config.on_click.clone(),
+            on_click_right: custom_config.on_click_right.clone(),
+            on_click_middle: custom_config.on_click_middle.clone(),
+            scroll_up: custom_config.scroll_up.clone(),
+            scroll_down: custom_config.scroll_down.clone(),
+            exec: custom_config.exec.clone(),
+            exec_if: custom_config.exec_if.clone(),
+            hide_empty: custom_config.hide_empty,
+            listen: custom_config.listen,
+            interval: custom_config.interval,
+            schedule: custom_config.schedule,
+            signal: custom_config.signal,
+            format: custom_config.format.clone(),
+            format_icons: custom_config.format_icons.clone(),
+            json: custom_config.return_type == "json",
+            tooltip: custom_config.tooltip,
+            tooltip_exec: custom_config.tooltip_exec.clone(),
+            tooltip_format: custom_config.tooltip_format.clone(),
+        });
+
+        widget.name = ctx.name.to_string();
+        CUSTOM_MODULES.with(|modules| {
+            modules
+                .borrow_mut()
+                .entry(widget.name.clone())
+                .or_default()
+                .push(widget.handle.clone());
+        });
+
+        Some(widget)
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.button.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+
+        CUSTOM_MODULES.with(|modules| {
+            let mut modules = modules.borrow_mut();
+            if let Some(built) = modules.get_mut(&self.name) {
+                built.retain(|handle| handle.output.button != self.button);
+                if built.is_empty() {
+                    modules.remove(&self.name);
+                }
+            }
+        });
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        if let Some(tooltip) = &self.tooltip {
+            state["tooltip"] = serde_json::json!(tooltip());
+        }
+        state
+    }
+}
+
+/// Show `text` on every custom module named `name` right away. A JSON
+/// object sets the tooltip and CSS classes as well, whatever the
+/// `return_type` of the module.
+pub fn update_custom(name: &str, text: &str) -> Result<(), String> {
+    let handles = custom_handles(name)?;
+
+    if text.trim_start().starts_with('{') {
+        let output: JsonOutput =
+            serde_json::from_str(text).map_err(|e| format!("invalid JSON output: {}", e))?;
+        handles
+            .iter()
+            .for_each(|handle| handle.output.show_json(&output));
+    } else {
+        handles
+            .iter()
+            .for_each(|handle| handle.output.show_text(text));
+    }
+    Ok(())
+}
+
+/// Run the `exec` of every custom module named `name` once, outside of its
+/// interval.
+pub fn refresh_custom(name: &str) -> Result<(), String> {
+    for handle in custom_handles(name)? {
+        let refreshed = handle
+            .refresh
+            .as_ref()
+            .is_some_and(|refresh| refresh.send(()).is_ok());
+        if !refreshed {
+            return Err(format!("'{}' has no exec to re-run", name));
+        }
+    }
+    Ok(())
+}
+
+fn custom_handles(name: &str) -> Result<Vec<CustomHandle>, String> {
+    CUSTOM_MODULES
+        .with(|modules| modules.borrow().get(name).cloned())
+        .ok_or_else(|| format!("no custom module named '{}'", name))
+}
+
+impl Output {
+    /// Show one output of `exec`, parsed according to the `return_type`.
+    fn show(&self, raw: &str) {
+        // Empty output is plain nothing, whatever the return type
+        if !self.json || raw.is_empty() {
+            return self.show_text(raw);
+        }
+
+        match serde_json::from_str::<JsonOutput>(raw) {
+            Ok(output) => self.show_json(&output),
+            Err(e) => {
+                eprintln!("[custom] invalid JSON output {:?}: {}", raw, e);
+                self.show_text(raw);
+            }
+        }
+    }
+
+    fn show_text(&self, text: &str) {
+        self.button
+            .set_visible(!(self.hide_empty && text.is_empty()));
+        let text = match &self.format {
+            Some(format) => format.replace("{}", text),
+            None => text.to_string(),
+        };
+        self.label.set_markup(&text);
+    }
+
+    fn show_json(&self, output: &JsonOutput) {
+        let text = output.text.as_deref().unwrap_or_default();
+        self.button
+            .set_visible(!(self.hide_empty && text.is_empty()));
+        let alt = output.alt.as_deref().unwrap_or_default();
+        let percentage = output
+            .percentage
+            .map(|percentage| percentage.round() as u32);
+        let icon = self
+            .format_icons
+            .as_ref()
+            .map(|icons| icons.icon(output.alt.as_deref(), percentage))
+            .unwrap_or_default();
+
+        let label = match &self.format {
+            Some(format) => format
+                .replace("{}", text)
+                .replace("{text}", text)
+                .replace("{alt}", alt)
+                .replace(
+                    "{percentage}",
+                    &percentage.map(|p| p.to_string()).unwrap_or_default(),
+                )
+                .replace("{icon}", icon),
+            None => text.to_string(),
+        };
+        self.label.set_markup(&label);
+        if self.json_tooltip {
+            self.button.set_tooltip_markup(output.tooltip.as_deref());
+        }
+
+        let classes = match &output.class {
+            Classes::None => Vec::new(),
+            Classes::One(class) => vec![class.clone()],
+            Classes::Many(classes) => classes.clone(),
+        };
+        let mut current = self.classes.borrow_mut();
+        for class in current.drain(..) {
+            self.button.remove_css_class(&class);
+        }
+        for class in &classes {
+            self.button.add_css_class(class);
+        }
+        *current = classes;
+    }
+}
+
+/// Whether `exec` should run: `exec_if` is unset or exits successfully.
+fn condition_met(exec_if: Option<&str>) -> bool {
+    exec_if.is_none_or(|exec_if| {
+        Command::new("sh")
+            .arg("-c")
+            .arg(exec_if)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    })
+}
+
+/// Time left until the next multiple of `period` in local wall-clock time,
+/// so `1m` ends at the start of a minute and `1h` at the start of an hour.
+fn until_boundary(period: Duration) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let offset = unsafe {
+        let time = now.as_secs() as libc::time_t;
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&time, &mut tm);
+        tm.tm_gmtoff
+    };
+
+    let local = (now.as_millis() as i128 + offset as i128 * 1000).max(0) as u128;
+    let period = period.as_millis().max(1);
+    Duration::from_millis((period - local % period) as u64)
+}
+
+/// Fill the tooltip of `button` when it is shown: `format` with `{}`
+/// replaced by the output of `exec`, or by the label without one. `exec`
+/// runs in the background and the tooltip updates once it finishes.
+///
+/// Returns the text last shown, for IPC state queries.
+fn connect_tooltip(
+    button: &gtk::Button,
+    label: &gtk::Label,
+    exec: Option<String>,
+    format: String,
+) -> Rc<dyn Fn() -> Option<String>> {
+    let cached: Rc<RefCell<Option<(Instant, String)>>> = Rc::default();
+    let running = Rc::new(Cell::new(false));
+    let label = label.clone();
+
+    let text: Rc<dyn Fn() -> Option<String>> = Rc::new({
+        let cached = cached.clone();
+        let label = label.clone();
+        let format = format.clone();
+        let has_exec = exec.is_some();
+        move || match cached.borrow().as_ref() {
+            Some((_, text)) => Some(format.replace("{}", text)),
+            None if !has_exec => Some(format.replace("{}", &label.label())),
+            None => None,
+        }
+    });
+
+    button.set_has_tooltip(true);
+    button.connect_query_tooltip(move |button, _, _, _, tooltip| {
+        let Some(exec) = &exec else {
+            tooltip.set_markup(Some(&format.replace("{}", &label.label())));
+            return true;
+        };
+
+        let stale = cached
+            .borrow()
+            .as_ref()
+            .is_none_or(|(time, _)| time.elapsed() > TOOLTIP_MAX_AGE);
+        if stale && !running.replace(true) {
+            let exec = exec.clone();
+            let cached = cached.clone();
+            let running = running.clone();
+            let button = button.clone();
+            glib::spawn_future_local(async move {
+                let output =
+                    gio::spawn_blocking(move || Command::new("sh").arg("-c").arg(&exec).output())
+                        .await;
+                match output {
+                    Ok(Ok(output)) => {
+                        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                        *cached.borrow_mut() = Some((Instant::now(), text));
+                    }
+                    Ok(Err(e)) => eprintln!("Custom module tooltip_exec failed: {}", e),
+                    Err(_) => {}
+                }
+                running.set(false);
+                button.trigger_tooltip_query();
+            });
+        }
+
+        match cached.borrow().as_ref() {
+            Some((_, text)) => {
+                tooltip.set_markup(Some(&format.replace("{}", text)));
+                true
+            }
+            None => false,
+        }
+    });
+
+    text
+}
+
 impl CustomModuleWidget {
     pub fn new(config: CustomModuleConfig) -> Self {
         let label = gtk::Label::new(None);
@@ -47,64 +410,139 @@ impl CustomModuleWidget {
             },
         );
 
-        let widget = Self {
+        let configured_tooltip = config.tooltip_exec.is_some()
+            || config.tooltip_format.is_some()
+            || (config.tooltip == Some(true) && !config.json);
+        let tooltip = (config.tooltip != Some(false) && configured_tooltip).then(|| {
+            connect_tooltip(
+                &button,
+                &label,
+                config.tooltip_exec.clone(),
+                config
+                    .tooltip_format
+                    .clone()
+                    .unwrap_or_else(|| "{}".to_string()),
+            )
+        });
+
+        let output = Output {
             button: button.clone(),
             label: label.clone(),
+            format: config.format,
+            format_icons: config.format_icons,
+            json: config.json,
+            hide_empty: config.hide_empty,
+            json_tooltip: config.json
+                && config.tooltip != Some(false)
+                && config.tooltip_exec.is_none()
+                && config.tooltip_format.is_none(),
+            classes: Rc::default(),
         };
+        let sources = SourceHandles::default();
 
-        if config.listen {
-            widget.start_listen(config.exec, config.format);
+        let refresh = if config.listen {
+            Self::start_listen(
+                &sources,
+                output.clone(),
+                config.exec,
+                config.exec_if,
+                config.interval,
+            );
+            None
         } else {
-            widget.start_updates(config.exec, config.interval, config.format);
-        }
-
-        widget
-    }
+            let refresh = Self::start_updates(
+                &sources,
+                output.clone(),
+                config.exec,
+                config.exec_if,
+                config.interval,
+                config.schedule,
+            );
+            if let (Some(signal), Some(refresh)) = (config.signal, &refresh) {
+                crate::shared::signals::subscribe(signal, refresh.clone());
+            }
+            refresh
+        };
 
-    pub fn widget(&self) -> &gtk::Button {
-        &self.button
+        Self {
+            name: format!("custom/{}", config.name),
+            button,
+            handle: CustomHandle { output, refresh },
+            tooltip,
+            sources,
+        }
     }
 
     // ── Polling mode ─────────────────────────────────────────────────────────
 
-    fn start_updates(&self, exec: String, interval: u64, format: Option<String>) {
-        let label = self.label.clone();
-        let (sender, receiver) = mpsc::channel::<String>();
+    /// Run `exec` every `interval` or on every `schedule` boundary, and
+    /// earlier whenever the returned sender is signalled. Returns `None` if
+    /// there is no `exec` to run.
+    fn start_updates(
+        sources: &SourceHandles,
+        output: Output,
+        exec: String,
+        exec_if: Option<String>,
+        interval: Duration,
+        schedule: Option<Duration>,
+    ) -> Option<mpsc::Sender<()>> {
+        if exec.is_empty() {
+            output.show_text("");
+            return None;
+        }
+
+        // `None` hides the module while `exec_if` fails
+        let (sender, receiver) = mpsc::channel::<Option<String>>();
+        let (refresh_tx, refresh_rx) = mpsc::channel::<()>();
 
         std::thread::spawn(move || {
             loop {
-                if !exec.is_empty() {
-                    let output = Command::new("sh").arg("-c").arg(&exec).output();
-
-                    match output {
-                        Ok(output) => {
-                            let result = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                            let formatted = if let Some(ref fmt) = format {
-                                fmt.replace("{}", &result)
-                            } else {
-                                result
-                            };
-                            let _ = sender.send(formatted);
-                        }
+                let result = if condition_met(exec_if.as_deref()) {
+                    match Command::new("sh").arg("-c").arg(&exec).output() {
+                        Ok(output) => Some(Some(
+                            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+                        )),
                         Err(e) => {
                             eprintln!("Custom module exec failed: {}", e);
+                            None
                         }
                     }
                 } else {
-                    let _ = sender.send(format.clone().unwrap_or_default());
+                    Some(None)
+                };
+
+                // Stop polling once the widget is gone
+                if let Some(result) = result
+                    && sender.send(result).is_err()
+                {
                     break;
                 }
 
-                sleep(std::time::Duration::from_secs(interval));
+                let wait = schedule.map(until_boundary).unwrap_or(interval);
+                match refresh_rx.recv_timeout(wait) {
+                    // Refreshes that piled up during the run count as one
+                    Ok(()) => while refresh_rx.try_recv().is_ok() {},
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
             }
         });
 
-        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-            if let Ok(msg) = receiver.try_recv() {
-                label.set_markup(&msg);
-            }
-            glib::ControlFlow::Continue
-        });
+        sources.push(glib::timeout_add_local(
+            Duration::from_millis(100),
+            move || {
+                // Intervals below the tick queue several results, only the
+                // newest is shown
+                match receiver.try_iter().last() {
+                    Some(Some(msg)) => output.show(&msg),
+                    Some(None) => output.button.set_visible(false),
+                    None => {}
+                }
+                glib::ControlFlow::Continue
+            },
+        ));
+
+        Some(refresh_tx)
     }
 
     // ── Listen mode ──────────────────────────────────────────────────────────
@@ -112,10 +550,18 @@ impl CustomModuleWidget {
     /// Spawn `exec` and read its stdout line by line.  Each non-empty line
     /// is sent to the GTK main thread as the new label text.  If the process
     /// exits for any reason it is restarted after a short back-off so a
-    /// crashing script doesn't spam the CPU.
-    fn start_listen(&self, exec: String, format: Option<String>) {
-        let label = self.label.clone();
-        let (sender, receiver) = mpsc::channel::<String>();
+    /// crashing script doesn't spam the CPU.  While `exec_if` fails the
+    /// module is hidden and the condition is checked again every `interval`.
+    fn start_listen(
+        sources: &SourceHandles,
+        output: Output,
+        exec: String,
+        exec_if: Option<String>,
+        interval: Duration,
+    ) {
+        // `None` hides the module while `exec_if` fails
+        let (sender, receiver) = mpsc::channel::<Option<String>>();
+        let hide_empty = output.hide_empty;
 
         std::thread::spawn(move || {
             if exec.is_empty() {
@@ -123,6 +569,14 @@ impl CustomModuleWidget {
             }
 
             loop {
+                if !condition_met(exec_if.as_deref()) {
+                    if sender.send(None).is_err() {
+                        return;
+                    }
+                    sleep(interval);
+                    continue;
+                }
+
                 // Spawn the script with its stdout piped.
                 let child = Command::new("sh")
                     .arg("-c")
@@ -137,7 +591,7 @@ impl CustomModuleWidget {
                     Err(e) => {
                         eprintln!("[custom/listen] failed to spawn '{}': {}", exec, e);
                         // Back off before retrying.
-                        sleep(std::time::Duration::from_secs(5));
+                        sleep(Duration::from_secs(5));
                         continue;
                     }
                 };
@@ -148,18 +602,14 @@ impl CustomModuleWidget {
                     for line in reader.lines() {
                         match line {
                             Ok(raw) => {
-                                let raw = raw.to_string();
-                                if raw.is_empty() {
+                                if raw.is_empty() && !hide_empty {
                                     continue;
                                 }
-                                let formatted = if let Some(ref fmt) = format {
-                                    fmt.replace("{}", &raw)
-                                } else {
-                                    raw
-                                };
                                 // If the receiver has been dropped (widget
                                 // destroyed), stop the thread silently.
-                                if sender.send(formatted).is_err() {
+                                if sender.send(Some(raw)).is_err() {
+                                    let _ = child.kill();
+                                    let _ = child.wait();
                                     return;
                                 }
                             }
@@ -178,23 +628,28 @@ impl CustomModuleWidget {
                     "[custom/listen] script '{}' exited, restarting in 2 seconds…",
                     exec
                 );
-                sleep(std::time::Duration::from_secs(2));
+                sleep(Duration::from_secs(2));
             }
         });
 
         // Poll the channel on the GTK main thread — same cadence as the
         // polling mode so there is at most ~100 ms of display lag.
-        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-            // Drain all pending lines; show only the most recent one so a
-            // fast-writing script doesn't stall the UI.
-            let mut last: Option<String> = None;
-            while let Ok(msg) = receiver.try_recv() {
-                last = Some(msg);
-            }
-            if let Some(msg) = last {
-                label.set_markup(&msg);
-            }
-            glib::ControlFlow::Continue
-        });
+        sources.push(glib::timeout_add_local(
+            Duration::from_millis(100),
+            move || {
+                // Drain all pending lines; show only the most recent one so a
+                // fast-writing script doesn't stall the UI.
+                let mut last: Option<Option<String>> = None;
+                while let Ok(msg) = receiver.try_recv() {
+                    last = Some(msg);
+                }
+                match last {
+                    Some(Some(msg)) => output.show(&msg),
+                    Some(None) => output.button.set_visible(false),
+                    None => {}
+                }
+                glib::ControlFlow::Continue
+            },
+        ));
     }
 }
@@ -1,4 +1,6 @@
 // ============ hyprland_workspaces.rs ============
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 use gtk4 as gtk;
 use gtk4::prelude::*;
 use hyprland::data::*;
@@ -51,6 +53,7 @@ impl WorkspacesConfig {
 
 pub struct HyprWorkspacesWidget {
     pub container: gtk::Box,
+    sources: SourceHandles,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -97,6 +100,7 @@ enum HyprEvent {
 }
 
 /// Connect to Hyprland's socket2 and forward relevant events down `tx`.
+/// The thread exits once the receiving widget is gone.
 fn start_socket_listener(tx: mpsc::Sender<HyprEvent>) {
     std::thread::spawn(move || {
         let instance = match std::env::var("HYPRLAND_INSTANCE_SIGNATURE") {
@@ -131,8 +135,10 @@ fn start_socket_listener(tx: mpsc::Sender<HyprEvent>) {
 
             // workspace>>ID  — active workspace switched
             if let Some(rest) = line.strip_prefix("workspace>>") {
-                if let Ok(id) = rest.trim().parse::<i32>() {
-                    let _ = tx.send(HyprEvent::ActiveWorkspace(id));
+                if let Ok(id) = rest.trim().parse::<i32>()
+                    && tx.send(HyprEvent::ActiveWorkspace(id)).is_err()
+                {
+                    return;
                 }
                 continue;
             }
@@ -141,27 +147,34 @@ fn start_socket_listener(tx: mpsc::Sender<HyprEvent>) {
             if let Some(rest) = line.strip_prefix("focusedmon>>") {
                 if let Some(id_str) = rest.split(',').nth(1)
                     && let Ok(id) = id_str.trim().parse::<i32>()
+                    && tx.send(HyprEvent::ActiveWorkspace(id)).is_err()
                 {
-                    let _ = tx.send(HyprEvent::ActiveWorkspace(id));
+                    return;
                 }
                 continue;
             }
 
             // createworkspace>>NAME  — a new workspace appeared
             if line.starts_with("createworkspace>>") {
-                let _ = tx.send(HyprEvent::WorkspaceListChanged);
+                if tx.send(HyprEvent::WorkspaceListChanged).is_err() {
+                    return;
+                }
                 continue;
             }
 
             // destroyworkspace>>NAME  — a workspace was removed
             if line.starts_with("destroyworkspace>>") {
-                let _ = tx.send(HyprEvent::WorkspaceListChanged);
+                if tx.send(HyprEvent::WorkspaceListChanged).is_err() {
+                    return;
+                }
                 continue;
             }
 
             // moveworkspace>>NAME,MONITOR  — workspace moved to another monitor
             if line.starts_with("moveworkspace>>") {
-                let _ = tx.send(HyprEvent::WorkspaceListChanged);
+                if tx.send(HyprEvent::WorkspaceListChanged).is_err() {
+                    return;
+                }
                 continue;
             }
 
@@ -171,7 +184,9 @@ fn start_socket_listener(tx: mpsc::Sender<HyprEvent>) {
             // "idleinhibitor>>ACTIVATE" / "idleinhibitor>>DEACTIVATE".
             if let Some(rest) = line.strip_prefix("idleinhibitor>>") {
                 let active = rest.trim().eq_ignore_ascii_case("activate");
-                let _ = tx.send(HyprEvent::Idle(active));
+                if tx.send(HyprEvent::Idle(active)).is_err() {
+                    return;
+                }
                 continue;
             }
         }
@@ -203,20 +218,37 @@ fn fetch_workspaces(show_special: bool) -> (Vec<WorkspaceObject>, i32) {
     (workspaces, active_id)
 }
 
+impl Module for HyprWorkspacesWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        let workspaces_config = Arc::new(WorkspacesConfig::from_config(
+            &ctx.instance_config(&ctx.config.workspaces),
+            ctx.orientation,
+        ));
+        Some(Self::new(workspaces_config))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.container.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+    }
+}
+
 impl HyprWorkspacesWidget {
     pub fn new(config: Arc<WorkspacesConfig>) -> Self {
         let container = gtk::Box::new(config.widget_orientation, 5);
         container.set_css_classes(&["workspaces"]);
 
-        let widget = Self { container };
+        let widget = Self {
+            container,
+            sources: SourceHandles::default(),
+        };
         widget.start_updates(config);
         widget
     }
 
-    pub fn widget(&self) -> &gtk::Box {
-        &self.container
-    }
-
     fn start_updates(&self, config: Arc<WorkspacesConfig>) {
         let container = self.container.clone();
         let (tx, rx) = mpsc::channel::<HyprEvent>();
@@ -250,88 +282,92 @@ impl HyprWorkspacesWidget {
 
         // Use idle_add so we only wake when there is actually something in the
         // channel, rather than hammering every 100 ms.
-        glib::timeout_add_local(std::time::Duration::from_millis(16), move || {
-            // Drain all pending events in one go
-            let mut needs_rebuild = false;
-            let mut new_active: Option<i32> = None;
-            let mut new_idle: Option<bool> = None;
-
-            while let Ok(event) = rx.try_recv() {
-                match event {
-                    HyprEvent::ActiveWorkspace(id) => {
-                        new_active = Some(id);
-                    }
-                    HyprEvent::WorkspaceListChanged => {
-                        needs_rebuild = true;
-                        // Also re-fetch the active id so the rebuild is correct
-                        new_active = Some(
-                            Workspace::get_active()
-                                .map(|w| w.id)
-                                .unwrap_or(current_active_id),
-                        );
-                    }
-                    HyprEvent::Idle(active) => {
-                        new_idle = Some(active);
+        self.sources.push(glib::timeout_add_local(
+            std::time::Duration::from_millis(16),
+            move || {
+                // Drain all pending events in one go
+                let mut needs_rebuild = false;
+                let mut new_active: Option<i32> = None;
+                let mut new_idle: Option<bool> = None;
+
+                while let Ok(event) = rx.try_recv() {
+                    match event {
+                        HyprEvent::ActiveWorkspace(id) => {
+                            new_active = Some(id);
+                        }
+                        HyprEvent::WorkspaceListChanged => {
+                            needs_rebuild = true;
+                            // Also re-fetch the active id so the rebuild is correct
+                            new_active = Some(
+                                Workspace::get_active()
+                                    .map(|w| w.id)
+                                    .unwrap_or(current_active_id),
+                            );
+                        }
+                        HyprEvent::Idle(active) => {
+                            new_idle = Some(active);
+                        }
                     }
                 }
-            }
 
-            // Apply idle CSS change
-            if let Some(idle) = new_idle
-                && idle != is_idle
-            {
-                is_idle = idle;
-                if idle {
-                    container.add_css_class("idle");
-                } else {
-                    container.remove_css_class("idle");
+                // Apply idle CSS change
+                if let Some(idle) = new_idle
+                    && idle != is_idle
+                {
+                    is_idle = idle;
+                    if idle {
+                        container.add_css_class("idle");
+                    } else {
+                        container.remove_css_class("idle");
+                    }
                 }
-            }
 
-            // Full rebuild only when workspace list changed
-            if needs_rebuild {
-                let (workspaces, fetched_active) = fetch_workspaces(config.show_special_workspaces);
-
-                // The new active id (from the socket event or a fresh fetch).
-                // We deliberately do NOT pass this into rebuild_buttons — the
-                // buttons are built without any active class so that GTK gets
-                // one clean frame to render them before the active style lands.
-                let next_active_id = if fetched_active != -1 {
-                    fetched_active
-                } else {
-                    new_active.unwrap_or(current_active_id)
-                };
-
-                // Rebuild with the *old* active id so no button starts life
-                // already marked active (avoids the instant-highlight glitch).
-                Self::rebuild_buttons(
-                    &container,
-                    &workspaces,
-                    current_active_id,
-                    config.format.as_deref().unwrap_or("{id}"),
-                    config.icons.clone(),
-                    config.min_workspace_count,
-                    &config.workspace_formating,
-                );
-
-                // One frame later: apply the active class to the correct button.
-                let container_rebuild = container.clone();
-                glib::timeout_add_local_once(std::time::Duration::from_millis(16), move || {
-                    Self::update_active_class(&container_rebuild, next_active_id);
-                });
-
-                current_active_id = next_active_id;
-            }
-            // Cheap active-class-only update — no rebuild needed
-            else if let Some(id) = new_active
-                && id != current_active_id
-            {
-                current_active_id = id;
-                Self::update_active_class(&container, id);
-            }
+                // Full rebuild only when workspace list changed
+                if needs_rebuild {
+                    let (workspaces, fetched_active) =
+                        fetch_workspaces(config.show_special_workspaces);
+
+                    // The new active id (from the socket event or a fresh fetch).
+                    // We deliberately do NOT pass this into rebuild_buttons — the
+                    // buttons are built without any active class so that GTK gets
+                    // one clean frame to render them before the active style lands.
+                    let next_active_id = if fetched_active != -1 {
+                        fetched_active
+                    } else {
+                        new_active.unwrap_or(current_active_id)
+                    };
+
+                    // Rebuild with the *old* active id so no button starts life
+                    // already marked active (avoids the instant-highlight glitch).
+                    Self::rebuild_buttons(
+                        &container,
+                        &workspaces,
+                        current_active_id,
+                        config.format.as_deref().unwrap_or("{id}"),
+                        config.icons.clone(),
+                        config.min_workspace_count,
+                        &config.workspace_formating,
+                    );
+
+                    // One frame later: apply the active class to the correct button.
+                    let container_rebuild = container.clone();
+                    glib::timeout_add_local_once(std::time::Duration::from_millis(16), move || {
+                        Self::update_active_class(&container_rebuild, next_active_id);
+                    });
+
+                    current_active_id = next_active_id;
+                }
+                // Cheap active-class-only update — no rebuild needed
+                else if let Some(id) = new_active
+                    && id != current_active_id
+                {
+                    current_active_id = id;
+                    Self::update_active_class(&container, id);
+                }
 
-            glib::ControlFlow::Continue
-        });
+                glib::ControlFlow::Continue
+            },
+        ));
     }
 
     fn rebuild_buttons(
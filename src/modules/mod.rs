@@ -6,21 +6,25 @@ mod box_module;
 mod clock;
 mod custom_module;
 mod hyprland_workspaces;
+mod module;
 mod mpris;
 mod network;
+mod registry;
 mod revealer;
 mod slider;
 mod tray;
 
-pub use active_window::{ActiveWindowConfig, ActiveWindowWidget};
-pub use audio::{AudioConfig, AudioWidget};
-pub use battery::{BatteryConfig, BatteryWidget};
-pub use box_module::{BoxWidget, BoxWidgetConfig};
-pub use clock::{ClockConfig, ClockWidget};
-pub use custom_module::{CustomModuleConfig, CustomModuleWidget};
-pub use hyprland_workspaces::{HyprWorkspacesWidget, WorkspacesConfig};
-pub use mpris::{MprisConfig, MprisWidget};
-pub use network::{NetworkConfig, NetworkWidget};
-pub use revealer::{RevealerConfig, RevealerWidget};
-pub use slider::{SliderModuleConfig, SliderModuleWidget};
-pub use tray::{TrayConfig, TrayWidget};
+pub use active_window::ActiveWindowWidget;
+pub use audio::{AudioWidget, MicrophoneWidget};
+pub use battery::BatteryWidget;
+pub use box_module::BoxWidget;
+pub use clock::ClockWidget;
+pub use custom_module::{CustomModuleWidget, refresh_custom, update_custom};
+pub use hyprland_workspaces::HyprWorkspacesWidget;
+pub use module::{Module, ModuleContext, widget_state};
+pub use mpris::MprisWidget;
+pub use network::NetworkWidget;
+pub use registry::{NamedModule, build_modules, registry};
+pub use revealer::{RevealerWidget, revealers, transition_type};
+pub use slider::SliderModuleWidget;
+pub use tray::TrayWidget;
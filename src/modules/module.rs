@@ -0,0 +1,95 @@
+// ============ modules/module.rs ============
+use super::NamedModule;
+use gtk4 as gtk;
+use gtk4::prelude::*;
+use serde::de::DeserializeOwned;
+
+/// Everything a module needs to build itself.
+pub struct ModuleContext<'a> {
+    /// Full module name as written in the config, e.g. `clock#utc`.
+    pub name: &'a str,
+    /// Part of the name after the registry prefix, e.g. `search` for
+    /// `custom/search`. Empty for modules matched by their exact name.
+    pub key: &'a str,
+    /// Instance suffix after `#`, e.g. `utc` for `clock#utc`.
+    pub instance: Option<&'a str>,
+    pub config: &'a crate::config::Config,
+    /// Orientation of the container the module is appended to.
+    pub orientation: gtk::Orientation,
+}
+
+impl ModuleContext<'_> {
+    /// Resolve the config table of this module, preferring the table of its
+    /// named instance over the shared `base` table.
+    pub fn instance_config<T: DeserializeOwned + Clone>(&self, base: &T) -> T {
+        if self.instance.is_some() {
+            self.config.instance_config(self.name, base)
+        } else {
+            base.clone()
+        }
+    }
+}
+
+/// Common interface of every bar module.
+///
+/// Modules are constructed from the config through `from_context` and hand
+/// their root widget to the container. `update` and `teardown` are optional
+/// hooks: the first forces a refresh outside of the module's own schedule,
+/// the second releases timers and threads before the widget is dropped.
+/// `state` describes the module for IPC queries and containers expose the
+/// modules built into them through `children`.
+pub trait Module {
+    /// Build the module, or return `None` if it has no usable configuration.
+    fn from_context(ctx: &ModuleContext) -> Option<Self>
+    where
+        Self: Sized;
+
+    fn widget(&self) -> gtk::Widget;
+
+    fn update(&self) {}
+
+    fn teardown(&self) {}
+
+    fn state(&self) -> serde_json::Value {
+        widget_state(&self.widget())
+    }
+
+    fn children(&self) -> &[NamedModule] {
+        &[]
+    }
+}
+
+/// Label text, tooltip and CSS classes currently shown by `widget`. Modules
+/// filling their tooltip on query or styling inner widgets override
+/// `Module::state` to report those.
+pub fn widget_state(widget: &gtk::Widget) -> serde_json::Value {
+    let mut labels = Vec::new();
+    collect_labels(widget, &mut labels);
+
+    serde_json::json!({
+        "label": labels.join(" "),
+        "tooltip": widget.tooltip_text().map(|tooltip| tooltip.to_string()),
+        "classes": widget
+            .css_classes()
+            .iter()
+            .map(|class| class.to_string())
+            .collect::<Vec<_>>(),
+        "visible": widget.is_visible(),
+    })
+}
+
+fn collect_labels(widget: &gtk::Widget, labels: &mut Vec<String>) {
+    if let Some(label) = widget.downcast_ref::<gtk::Label>() {
+        let text = label.text();
+        if !text.is_empty() {
+            labels.push(text.to_string());
+        }
+        return;
+    }
+
+    let mut child = widget.first_child();
+    while let Some(widget) = child {
+        collect_labels(&widget, labels);
+        child = widget.next_sibling();
+    }
+}
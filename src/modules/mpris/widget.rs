@@ -9,9 +9,15 @@ use std::{
 use tokio::runtime::Runtime;
 
 use crate::modules::mpris::dbus_util::{self, wait_for_active_player /*, get_active_player*/};
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 
 pub struct MprisWidget {
     pub button: gtk::Button,
+    media_info: Arc<Mutex<MediaInfo>>,
+    /// Format of the tooltip, `None` when it is disabled.
+    tooltip_format: Option<String>,
+    sources: SourceHandles,
 }
 
 #[derive(Clone)]
@@ -21,7 +27,7 @@ pub struct MprisConfig {
     pub format_stopped: String,
     pub format_nothing: String,
     pub length_lim: u64,
-    pub interval: u64,
+    pub interval: Duration,
     pub tooltip: bool,
     pub tooltip_format: String,
 }
@@ -34,7 +40,7 @@ impl Default for MprisConfig {
             format_stopped: "{icon} Stopped".to_string(),
             format_nothing: "No Media".to_string(),
             length_lim: 0,
-            interval: 100,
+            interval: Duration::from_millis(100),
             tooltip: true,
             tooltip_format: "{artist}\n{album}\n{title}".to_string(),
         }
@@ -64,6 +70,32 @@ struct MediaInfo {
     status: String,
 }
 
+impl Module for MprisWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        let mut mpris_config = ctx.instance_config(&ctx.config.mpris);
+        mpris_config.normalize();
+        Some(Self::new(MprisConfig::from_config(&mpris_config)))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.button.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        state["tooltip"] = serde_json::json!(
+            self.tooltip_format
+                .as_ref()
+                .map(|format| { tooltip_text(format, &self.media_info.lock().unwrap()) })
+        );
+        state
+    }
+}
+
 impl MprisWidget {
     pub fn new(config: MprisConfig) -> Self {
         // Media button
@@ -84,7 +116,18 @@ impl MprisWidget {
             },
         );
 
-        let widget = Self { button };
+        let widget = Self {
+            button,
+            // Use Arc<Mutex> for thread-safe sharing of MediaInfo
+            media_info: Arc::new(Mutex::new(MediaInfo {
+                artist: String::new(),
+                title: String::new(),
+                album: String::new(),
+                status: String::from("Stopped"),
+            })),
+            tooltip_format: config.tooltip.then(|| config.tooltip_format.clone()),
+            sources: SourceHandles::default(),
+        };
 
         // Start the update loop
         widget.start_updates(config);
@@ -92,22 +135,12 @@ impl MprisWidget {
         widget
     }
 
-    pub fn widget(&self) -> &gtk::Button {
-        &self.button
-    }
-
     fn start_updates(&self, config: MprisConfig) {
         let button = self.button.clone();
         let (label_sender, label_receiver) = mpsc::channel::<String>();
         let (state_sender, state_receiver) = mpsc::channel::<String>();
 
-        // Use Arc<Mutex> for thread-safe sharing of MediaInfo
-        let media_info = Arc::new(Mutex::new(MediaInfo {
-            artist: String::new(),
-            title: String::new(),
-            album: String::new(),
-            status: String::from("Stopped"),
-        }));
+        let media_info = self.media_info.clone();
 
         let interval = config.interval;
         let format_playing = config.format_playing;
@@ -126,7 +159,7 @@ impl MprisWidget {
                     Ok(pf) => pf,
                     Err(e) => {
                         eprintln!("[MPRIS]: Could not connect to D-Bus \n ERROR:{}", e);
-                        std::thread::sleep(Duration::from_millis(interval));
+                        std::thread::sleep(interval);
                         return "";
                     }
                 };
@@ -136,7 +169,7 @@ impl MprisWidget {
                     match player_finder.find_active() {
                         Ok(p) => break p,
                         Err(_) => {
-                            std::thread::sleep(std::time::Duration::from_millis(interval * 4));
+                            std::thread::sleep(interval * 4);
                             let _ = state_sender.send("Nothing".to_string());
                             let _ = label_sender.send(format_nothing.to_string());
                             player_name = wait_for_active_player(&dbus_obj.conn, Some(950));
@@ -153,9 +186,7 @@ impl MprisWidget {
                             match player_finder.find_active() {
                                 Ok(p) => break p,
                                 Err(_) => {
-                                    std::thread::sleep(std::time::Duration::from_millis(
-                                        interval * 4,
-                                    ));
+                                    std::thread::sleep(interval * 4);
                                     let _ = state_sender.send("Nothing".to_string());
                                     let _ = label_sender.send(format_nothing.to_string());
                                     player_name = wait_for_active_player(&dbus_obj.conn, None);
@@ -229,10 +260,13 @@ impl MprisWidget {
                     };
 
                     let _ = state_sender.send(playback_status.to_string());
-                    let _ = label_sender.send(display);
+                    // Stop following the player once the widget is gone
+                    if label_sender.send(display).is_err() {
+                        return "";
+                    }
                     let _ = playback_status;
                     drop(pre_display);
-                    std::thread::sleep(std::time::Duration::from_millis(interval));
+                    std::thread::sleep(interval);
 
                     for event_result in player.events().unwrap() {
                         if let Err(e) = event_result {
@@ -266,41 +300,35 @@ impl MprisWidget {
 
             button.connect_query_tooltip(move |_, _, _, _, tooltip| {
                 let info = media_info_tooltip.lock().unwrap();
-                if !info.title.is_empty() {
-                    let tooltip_text = tooltip_format
-                        .replace("{artist}", &info.artist)
-                        .replace("{title}", &info.title)
-                        .replace("{album}", &info.album)
-                        .replace("{status}", &info.status);
-                    tooltip.set_text(Some(&tooltip_text));
-                    return true;
-                }
-                tooltip.set_text(Some("No media playing"));
+                tooltip.set_text(Some(&tooltip_text(&tooltip_format, &info)));
                 true
             });
         }
 
         // Poll for updates
-        glib::timeout_add_local(std::time::Duration::from_millis(300), move || {
-            if let Ok(metadata) = label_receiver.try_recv() {
-                button.set_label(&metadata);
-            }
-
-            if let Ok(state) = state_receiver.try_recv() {
-                let class = if state == "Playing" {
-                    "mpris playing"
-                } else if state == "Paused" {
-                    "mpris paused"
-                } else {
-                    "mpris stopped"
-                };
-                let classes: Vec<&str> = class.split(' ').collect();
-                button.set_css_classes(&classes);
-                button.add_css_class("module");
-            }
+        self.sources.push(glib::timeout_add_local(
+            std::time::Duration::from_millis(300),
+            move || {
+                if let Ok(metadata) = label_receiver.try_recv() {
+                    button.set_label(&metadata);
+                }
 
-            glib::ControlFlow::Continue
-        });
+                if let Ok(state) = state_receiver.try_recv() {
+                    let class = if state == "Playing" {
+                        "mpris playing"
+                    } else if state == "Paused" {
+                        "mpris paused"
+                    } else {
+                        "mpris stopped"
+                    };
+                    let classes: Vec<&str> = class.split(' ').collect();
+                    button.set_css_classes(&classes);
+                    button.add_css_class("module");
+                }
+
+                glib::ControlFlow::Continue
+            },
+        ));
     }
 }
 
@@ -321,3 +349,14 @@ fn get_string_from_metadata<'a>(metadata: &'a mpris::Metadata, key: &str) -> &'a
         })
         .unwrap_or("")
 }
+
+fn tooltip_text(format: &str, info: &MediaInfo) -> String {
+    if info.title.is_empty() {
+        return "No media playing".to_string();
+    }
+    format
+        .replace("{artist}", &info.artist)
+        .replace("{title}", &info.title)
+        .replace("{album}", &info.album)
+        .replace("{status}", &info.status)
+}
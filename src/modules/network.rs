@@ -1,4 +1,6 @@
 // ============ modules/network.rs ============
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 use crate::shared::{Gestures, create_gesture_handler};
 use gtk4 as gtk;
 use gtk4::prelude::*;
@@ -9,6 +11,9 @@ use std::{
 
 pub struct NetworkWidget {
     button: gtk::Button,
+    network_info: Arc<Mutex<NetworkInfo>>,
+    tooltip: bool,
+    sources: SourceHandles,
 }
 
 #[derive(Clone)]
@@ -18,7 +23,7 @@ pub struct NetworkConfig {
     pub ethernet_icon: Option<String>,
     pub disconnected_icon: Option<String>,
     pub gestures: Gestures,
-    pub interval: u64,
+    pub interval: std::time::Duration,
     pub interface: String,
     pub tooltip: bool,
 }
@@ -63,7 +68,7 @@ impl Default for NetworkConfig {
             active_icons: crate::config::NetworkConfig::default_active_icons(),
             ethernet_icon: None,
             disconnected_icon: None,
-            interval: 5,
+            interval: std::time::Duration::from_secs(5),
             interface: String::from("wlan0"),
             tooltip: true,
         }
@@ -80,6 +85,31 @@ struct NetworkInfo {
     ip_address: String,
 }
 
+impl Module for NetworkWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        Some(Self::new(Arc::new(NetworkConfig::from_config(
+            &ctx.instance_config(&ctx.config.network),
+        ))))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.button.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        state["tooltip"] = serde_json::json!(
+            self.tooltip
+                .then(|| tooltip_text(&self.network_info.lock().unwrap()))
+        );
+        state
+    }
+}
+
 impl NetworkWidget {
     pub fn new(config: Arc<NetworkConfig>) -> Self {
         let button = gtk::Button::with_label("󰌙");
@@ -109,44 +139,47 @@ impl NetworkWidget {
         let config_clone = Arc::clone(&config);
         let network_info_clone = network_info.clone();
 
-        glib::timeout_add_seconds_local(config.interval as u32, move || {
+        let sources = SourceHandles::default();
+        sources.push(glib::timeout_add_local(config.interval, move || {
             let info = get_network_info(&config_clone.interface);
             *network_info_clone.lock().unwrap() = info.clone();
             update_button(&button_clone, &info, &config_clone);
             glib::ControlFlow::Continue
-        });
+        }));
 
         // Add tooltip if enabled
         if config.tooltip {
             let network_info_clone = network_info.clone();
             button.set_has_tooltip(true);
             button.connect_query_tooltip(move |_, _, _, _, tooltip| {
-                let info = network_info_clone.lock().unwrap();
-                if info.connected {
-                    let tooltip_text = if info.is_ethernet {
-                        format!(
-                            "Interface: {}\nType: Ethernet\nIP: {}",
-                            info.interface, info.ip_address
-                        )
-                    } else {
-                        format!(
-                            "SSID: {}\nSignal: {}%\nInterface: {}\nIP: {}",
-                            info.essid, info.signal_strength, info.interface, info.ip_address
-                        )
-                    };
-                    tooltip.set_text(Some(&tooltip_text));
-                } else {
-                    tooltip.set_text(Some("Disconnected"));
-                }
+                tooltip.set_text(Some(&tooltip_text(&network_info_clone.lock().unwrap())));
                 true
             });
         }
 
-        Self { button }
+        Self {
+            button,
+            network_info,
+            tooltip: config.tooltip,
+            sources,
+        }
     }
+}
 
-    pub fn widget(&self) -> &gtk::Button {
-        &self.button
+fn tooltip_text(info: &NetworkInfo) -> String {
+    if !info.connected {
+        return "Disconnected".to_string();
+    }
+    if info.is_ethernet {
+        format!(
+            "Interface: {}\nType: Ethernet\nIP: {}",
+            info.interface, info.ip_address
+        )
+    } else {
+        format!(
+            "SSID: {}\nSignal: {}%\nInterface: {}\nIP: {}",
+            info.essid, info.signal_strength, info.interface, info.ip_address
+        )
     }
 }
 
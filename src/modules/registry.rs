@@ -0,0 +1,206 @@
+// ============ modules/registry.rs ============
+use super::{Module, ModuleContext};
+use gtk4 as gtk;
+use gtk4::prelude::*;
+use once_cell::sync::Lazy;
+use std::ops::Deref;
+
+type ModuleFactory = fn(&ModuleContext) -> Option<Box<dyn Module>>;
+
+/// Maps module names from the config to their constructors.
+///
+/// Names ending in `/` are prefixes (`custom/`, `box/`, …) and match every
+/// module that starts with them; all other names must match exactly. A
+/// `#instance` suffix (`clock#utc`) is ignored for the lookup.
+pub struct ModuleRegistry {
+    entries: Vec<(&'static str, ModuleFactory)>,
+}
+
+/// A built module together with its name from the config, e.g. `clock#utc`.
+pub struct NamedModule {
+    pub name: String,
+    module: Box<dyn Module>,
+}
+
+impl Deref for NamedModule {
+    type Target = dyn Module;
+
+    fn deref(&self) -> &Self::Target {
+        self.module.as_ref()
+    }
+}
+
+impl NamedModule {
+    /// Find `name` among this module and the modules nested inside it.
+    pub fn find(&self, name: &str) -> Option<&NamedModule> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children().iter().find_map(|child| child.find(name))
+    }
+
+    /// Names of this module and every module nested inside it, depth first.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![self.name.clone()];
+        for child in self.children() {
+            names.extend(child.names());
+        }
+        names
+    }
+}
+
+static REGISTRY: Lazy<ModuleRegistry> = Lazy::new(ModuleRegistry::default);
+
+/// Registry holding every built-in module.
+pub fn registry() -> &'static ModuleRegistry {
+    &REGISTRY
+}
+
+fn construct<M: Module + 'static>(ctx: &ModuleContext) -> Option<Box<dyn Module>> {
+    M::from_context(ctx).map(|module| Box::new(module) as Box<dyn Module>)
+}
+
+impl Default for ModuleRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            entries: Vec::new(),
+        };
+
+        registry.register::<super::ClockWidget>("clock");
+        registry.register::<super::TrayWidget>("tray");
+        registry.register::<super::HyprWorkspacesWidget>("hyprland/workspaces");
+        registry.register::<super::ActiveWindowWidget>("active_window");
+        registry.register::<super::MprisWidget>("mpris");
+        registry.register::<super::NetworkWidget>("network");
+        registry.register::<super::BatteryWidget>("battery");
+        registry.register::<super::AudioWidget>("audio");
+        registry.register::<super::MicrophoneWidget>("microphone");
+        registry.register::<super::CustomModuleWidget>("custom/");
+        registry.register::<super::SliderModuleWidget>("sliders/");
+        registry.register::<super::BoxWidget>("box/");
+        registry.register::<super::RevealerWidget>("revealer/");
+
+        registry
+    }
+}
+
+impl ModuleRegistry {
+    /// Register a module under an exact name or, with a trailing `/`, a prefix.
+    pub fn register<M: Module + 'static>(&mut self, name: &'static str) {
+        self.entries.push((name, construct::<M>));
+    }
+
+    /// Find the constructor for `name` and the key left after its prefix.
+    fn lookup<'a>(&self, name: &'a str) -> Option<(ModuleFactory, &'a str)> {
+        self.entries.iter().find_map(|(entry, factory)| {
+            if entry.ends_with('/') {
+                name.strip_prefix(entry).map(|key| (*factory, key))
+            } else if *entry == name {
+                Some((*factory, ""))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Whether `name` resolves to a module, ignoring its `#instance` suffix.
+    pub fn contains(&self, name: &str) -> bool {
+        let base = name.split('#').next().unwrap_or_default();
+        self.lookup(base).is_some()
+    }
+
+    pub fn build(
+        &self,
+        name: &str,
+        config: &crate::config::Config,
+        orientation: gtk::Orientation,
+    ) -> Option<Box<dyn Module>> {
+        let (base, instance) = match name.split_once('#') {
+            Some((base, instance)) => (base, Some(instance)),
+            None => (name, None),
+        };
+
+        let Some((factory, key)) = self.lookup(base) else {
+            eprintln!("Unknown module: {}", name);
+            return None;
+        };
+
+        let module = factory(&ModuleContext {
+            name,
+            key,
+            instance,
+            config,
+            orientation,
+        });
+
+        match &module {
+            Some(module) => {
+                let widget = module.widget();
+                if let Some(instance) = instance {
+                    widget.add_css_class(instance);
+                }
+                widget.add_controller(click_events(name));
+            }
+            None => eprintln!("No configuration found for module: {}", name),
+        }
+
+        module
+    }
+}
+
+/// Observe clicks on a module for IPC subscribers. Runs in the capture phase
+/// and never claims the click, so the module's own handlers still fire.
+fn click_events(name: &str) -> gtk::GestureClick {
+    let gesture = gtk::GestureClick::new();
+    gesture.set_button(0);
+    gesture.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+    let name = name.to_string();
+    gesture.connect_pressed(move |gesture, _, _, _| {
+        let button = match gesture.current_button() {
+            1 => "left",
+            2 => "middle",
+            3 => "right",
+            _ => return,
+        };
+        crate::ipc::emit(
+            "click",
+            serde_json::json!({ "module": name, "button": button }),
+        );
+    });
+
+    gesture
+}
+
+/// Build `module_names` through the registry and append them to `container`.
+/// Returns the built modules so the caller can keep their hooks around.
+pub fn build_modules(
+    container: &gtk::Box,
+    module_names: &[String],
+    config: &crate::config::Config,
+    container_type: i32,
+) -> Vec<NamedModule> {
+    let container_name = match container_type {
+        0 => "",
+        1 => " in box",
+        2 => " in revealer",
+        _ => "",
+    };
+
+    println!("Building modules{}: {:?}", container_name, module_names);
+
+    let container_orientation = container.orientation();
+    let mut modules = Vec::new();
+
+    for name in module_names {
+        if let Some(module) = registry().build(name, config, container_orientation) {
+            container.append(&module.widget());
+            modules.push(NamedModule {
+                name: name.clone(),
+                module,
+            });
+        }
+    }
+
+    modules
+}
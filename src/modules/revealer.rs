@@ -1,11 +1,27 @@
 // ============ modules/revealer.rs ============
 
+use crate::modules::{Module, ModuleContext, NamedModule};
 use gtk4 as gtk;
 use gtk4::prelude::*;
-use std::sync::{Arc, Mutex};
+use std::{cell::RefCell, collections::HashMap};
+
+thread_local! {
+    // Built revealers by module name, e.g. `revealer/tray`, for IPC. The
+    // same revealer can appear on several bars.
+    static REVEALERS: RefCell<HashMap<String, Vec<gtk::Revealer>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Every revealer currently built for the module `name`.
+pub fn revealers(name: &str) -> Vec<gtk::Revealer> {
+    REVEALERS.with(|revealers| revealers.borrow().get(name).cloned().unwrap_or_default())
+}
 
 pub struct RevealerWidget {
+    name: String,
     container: gtk::Box,
+    revealer: gtk::Revealer,
+    modules: Vec<NamedModule>,
 }
 
 #[derive(Clone)]
@@ -33,6 +49,90 @@ impl Default for RevealerConfig {
     }
 }
 
+impl RevealerConfig {
+    pub fn from_config(config: &crate::config::RevealerConfig) -> Self {
+        Self {
+            modules: config.modules.clone(),
+            spacing: config.spacing,
+            orientation: config
+                .orientation
+                .clone()
+                .unwrap_or_else(|| "horizontal".to_string()),
+            trigger: config.trigger.clone().unwrap_or_default(),
+            transition: config
+                .transition
+                .clone()
+                .unwrap_or_else(|| "slide_left".to_string()),
+            transition_duration: config.transition_duration.unwrap_or(200),
+            reveal_on_hover: config.reveal_on_hover.unwrap_or(false),
+        }
+    }
+}
+
+impl Module for RevealerWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        let revealer_config = &ctx.instance_config(ctx.config.revealers.get(ctx.key)?);
+        let mut widget = Self::new(
+            ctx.key,
+            RevealerConfig::from_config(revealer_config),
+            ctx.config,
+        );
+
+        widget.name = ctx.name.to_string();
+        // Full name, so subscribers can send it back to `reveal`
+        widget.revealer.connect_reveal_child_notify({
+            let name = widget.name.clone();
+            move |revealer| {
+                crate::ipc::emit(
+                    "revealer",
+                    serde_json::json!({ "name": name, "revealed": revealer.reveals_child() }),
+                );
+            }
+        });
+        REVEALERS.with(|revealers| {
+            revealers
+                .borrow_mut()
+                .entry(widget.name.clone())
+                .or_default()
+                .push(widget.revealer.clone());
+        });
+
+        Some(widget)
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.container.clone().upcast()
+    }
+
+    fn update(&self) {
+        self.modules.iter().for_each(|module| module.update());
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        state["revealed"] = self.revealer.reveals_child().into();
+        state
+    }
+
+    fn children(&self) -> &[NamedModule] {
+        &self.modules
+    }
+
+    fn teardown(&self) {
+        self.modules.iter().for_each(|module| module.teardown());
+
+        REVEALERS.with(|revealers| {
+            let mut revealers = revealers.borrow_mut();
+            if let Some(built) = revealers.get_mut(&self.name) {
+                built.retain(|revealer| revealer != &self.revealer);
+                if built.is_empty() {
+                    revealers.remove(&self.name);
+                }
+            }
+        });
+    }
+}
+
 impl RevealerWidget {
     pub fn new(name: &str, config: RevealerConfig, app_config: &crate::config::Config) -> Self {
         // Main container
@@ -49,27 +149,18 @@ impl RevealerWidget {
         revealer.set_transition_duration(config.transition_duration);
 
         // Set transition type
-        let transition_type = match config.transition.as_str() {
-            "slide_left" => gtk::RevealerTransitionType::SlideLeft,
-            "slide_right" => gtk::RevealerTransitionType::SlideRight,
-            "slide_up" => gtk::RevealerTransitionType::SlideUp,
-            "slide_down" => gtk::RevealerTransitionType::SlideDown,
-            "crossfade" => gtk::RevealerTransitionType::Crossfade,
-            _ => gtk::RevealerTransitionType::SlideLeft,
-        };
-        revealer.set_transition_type(transition_type);
+        revealer.set_transition_type(
+            transition_type(&config.transition).unwrap_or(gtk::RevealerTransitionType::SlideLeft),
+        );
 
         // Content box that will be revealed
         let content_box = gtk::Box::new(widget_orientation, config.spacing);
         content_box.add_css_class("revealer-content");
 
         // Build modules in the content box
-        crate::build_modules(&content_box, &config.modules, app_config, 2);
+        let modules = crate::modules::build_modules(&content_box, &config.modules, app_config, 2);
         revealer.set_child(Some(&content_box));
 
-        // State tracking
-        let is_revealed = Arc::new(Mutex::new(false));
-
         // If there's a trigger, create it
         if !config.trigger.is_empty() {
             let trigger_button = gtk::Button::with_label(&config.trigger);
@@ -77,11 +168,8 @@ impl RevealerWidget {
 
             // Toggle on click
             let revealer_clone = revealer.clone();
-            let is_revealed_clone = is_revealed.clone();
             trigger_button.connect_clicked(move |_| {
-                let mut revealed = is_revealed_clone.lock().unwrap();
-                *revealed = !*revealed;
-                revealer_clone.set_reveal_child(*revealed);
+                revealer_clone.set_reveal_child(!revealer_clone.reveals_child());
             });
 
             container.append(&trigger_button);
@@ -91,10 +179,8 @@ impl RevealerWidget {
         if config.reveal_on_hover {
             let hover_controller = gtk::EventControllerMotion::new();
             let revealer_hover = revealer.clone();
-            let is_revealed_hover = is_revealed.clone();
 
             hover_controller.connect_enter(move |_, _, _| {
-                *is_revealed_hover.lock().unwrap() = true;
                 revealer_hover.set_reveal_child(true);
             });
 
@@ -102,10 +188,8 @@ impl RevealerWidget {
 
             let leave_controller = gtk::EventControllerMotion::new();
             let revealer_leave = revealer.clone();
-            let is_revealed_leave = is_revealed.clone();
 
             leave_controller.connect_leave(move |_| {
-                *is_revealed_leave.lock().unwrap() = false;
                 revealer_leave.set_reveal_child(false);
             });
 
@@ -114,10 +198,23 @@ impl RevealerWidget {
 
         container.append(&revealer);
 
-        Self { container }
+        Self {
+            name: format!("revealer/{}", name),
+            container,
+            revealer,
+            modules,
+        }
     }
+}
 
-    pub fn widget(&self) -> &gtk::Box {
-        &self.container
-    }
+/// Revealer transition named in the config, e.g. `slide_left`.
+pub fn transition_type(name: &str) -> Option<gtk::RevealerTransitionType> {
+    Some(match name {
+        "slide_left" => gtk::RevealerTransitionType::SlideLeft,
+        "slide_right" => gtk::RevealerTransitionType::SlideRight,
+        "slide_up" => gtk::RevealerTransitionType::SlideUp,
+        "slide_down" => gtk::RevealerTransitionType::SlideDown,
+        "crossfade" => gtk::RevealerTransitionType::Crossfade,
+        _ => return None,
+    })
 }
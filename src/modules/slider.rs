@@ -1,13 +1,19 @@
 // ============ slider.rs ============
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 use gtk4 as gtk;
 use gtk4::prelude::*;
 use std::io::{BufRead, BufReader};
 use std::process::{Command, Stdio};
 use std::sync::mpsc;
 use std::thread::sleep;
+use std::time::Duration;
 
 pub struct SliderModuleWidget {
     slider: gtk::Scale,
+    /// Format of the tooltip, `None` when it is disabled.
+    tooltip_format: Option<String>,
+    sources: SourceHandles,
 }
 
 pub struct SliderModuleConfig<'a> {
@@ -20,7 +26,47 @@ pub struct SliderModuleConfig<'a> {
     pub scroll_step: u32,
     pub scroll_cmd: String,
     pub exec: String,
-    pub interval: u64,
+    pub interval: Duration,
+    /// Re-run `exec` on `SIGRTMIN+signal`, see `shared::signals`.
+    pub signal: Option<u32>,
+    pub tooltip: Option<bool>,
+    pub tooltip_format: Option<String>,
+}
+
+impl Module for SliderModuleWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        let slider_config = &ctx.instance_config(ctx.config.sliders.get(ctx.key)?);
+        Some(Self::new(SliderModuleConfig {
+            name: ctx.key,
+            exec: slider_config.exec.clone(),
+            listen: slider_config.listen,
+            length: slider_config.length,
+            interval: slider_config.interval,
+            signal: slider_config.signal,
+            tooltip: slider_config.tooltip,
+            tooltip_format: slider_config.tooltip_format.clone(),
+            scroll_cmd: slider_config.scroll_cmd.clone(),
+            scroll_step: slider_config.scroll_step,
+        }))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.slider.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.sources.remove_all();
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let mut state = crate::modules::widget_state(&self.widget());
+        state["tooltip"] = serde_json::json!(
+            self.tooltip_format
+                .as_ref()
+                .map(|format| tooltip_text(format, self.slider.value()))
+        );
+        state
+    }
 }
 
 impl SliderModuleWidget {
@@ -37,6 +83,18 @@ impl SliderModuleWidget {
         slider.add_css_class("slider-module");
         slider.add_css_class(&format!("slider-{}", config.name));
 
+        // Show the current value on hover
+        let tooltip_format = (config.tooltip == Some(true)
+            || (config.tooltip.is_none() && config.tooltip_format.is_some()))
+        .then(|| config.tooltip_format.unwrap_or_else(|| "{}".to_string()));
+        if let Some(tooltip_format) = tooltip_format.clone() {
+            slider.set_has_tooltip(true);
+            slider.connect_query_tooltip(move |slider, _, _, _, tooltip| {
+                tooltip.set_markup(Some(&tooltip_text(&tooltip_format, slider.value())));
+                true
+            });
+        }
+
         // Create gesture handlers
         // Run a command if value of scale changes
         if !config.scroll_cmd.is_empty() {
@@ -55,26 +113,28 @@ impl SliderModuleWidget {
 
         let widget = Self {
             slider: slider.clone(),
+            tooltip_format,
+            sources: SourceHandles::default(),
         };
 
         if config.listen {
             widget.start_listen(config.exec);
         } else {
-            widget.start_updates(config.exec, config.interval);
+            widget.start_updates(config.exec, config.interval, config.signal);
         }
 
         widget
     }
 
-    pub fn widget(&self) -> &gtk::Scale {
-        &self.slider
-    }
-
     // ── Polling mode ─────────────────────────────────────────────────────────
 
-    fn start_updates(&self, exec: String, interval: u64) {
+    fn start_updates(&self, exec: String, interval: Duration, signal: Option<u32>) {
         let slider = self.slider.clone();
         let (sender, receiver) = mpsc::channel::<f64>();
+        let (refresh_tx, refresh_rx) = mpsc::channel::<()>();
+        if let Some(signal) = signal {
+            crate::shared::signals::subscribe(signal, refresh_tx);
+        }
 
         std::thread::spawn(move || {
             loop {
@@ -85,7 +145,10 @@ impl SliderModuleWidget {
                         Ok(output) => {
                             let result = String::from_utf8_lossy(&output.stdout).trim().to_string();
                             let formatted: f64 = result.parse().unwrap_or(0.0);
-                            let _ = sender.send(formatted);
+                            // Stop polling once the widget is gone
+                            if sender.send(formatted).is_err() {
+                                break;
+                            }
                         }
                         Err(e) => {
                             eprintln!("Custom module exec failed: {}", e);
@@ -96,16 +159,26 @@ impl SliderModuleWidget {
                     break;
                 }
 
-                sleep(std::time::Duration::from_secs(interval));
+                // Sleep until the next interval or an earlier signal
+                match refresh_rx.recv_timeout(interval) {
+                    Ok(()) => while refresh_rx.try_recv().is_ok() {},
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    // No signal to wait for, or it was rejected
+                    Err(mpsc::RecvTimeoutError::Disconnected) => sleep(interval),
+                }
             }
         });
 
-        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-            if let Ok(msg) = receiver.try_recv() {
-                slider.set_value(msg);
-            }
-            glib::ControlFlow::Continue
-        });
+        self.sources.push(glib::timeout_add_local(
+            Duration::from_millis(100),
+            move || {
+                // Intervals below the tick queue several values
+                if let Some(msg) = receiver.try_iter().last() {
+                    slider.set_value(msg);
+                }
+                glib::ControlFlow::Continue
+            },
+        ));
     }
 
     // ── Listen mode ──────────────────────────────────────────────────────────
@@ -158,6 +231,8 @@ impl SliderModuleWidget {
                                 // destroyed), stop the thread silently.
 
                                 if sender.send(formatted).is_err() {
+                                    let _ = child.kill();
+                                    let _ = child.wait();
                                     return;
                                 }
                             }
@@ -182,17 +257,24 @@ impl SliderModuleWidget {
 
         // Poll the channel on the GTK main thread — same cadence as the
         // polling mode so there is at most ~100 ms of display lag.
-        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-            // Drain all pending lines; show only the most recent one so a
-            // fast-writing script doesn't stall the UI.
-            let mut last: Option<f64> = None;
-            while let Ok(msg) = receiver.try_recv() {
-                last = Some(msg);
-            }
-            if let Some(msg) = last {
-                slider.set_value(msg);
-            }
-            glib::ControlFlow::Continue
-        });
+        self.sources.push(glib::timeout_add_local(
+            std::time::Duration::from_millis(100),
+            move || {
+                // Drain all pending lines; show only the most recent one so a
+                // fast-writing script doesn't stall the UI.
+                let mut last: Option<f64> = None;
+                while let Ok(msg) = receiver.try_recv() {
+                    last = Some(msg);
+                }
+                if let Some(msg) = last {
+                    slider.set_value(msg);
+                }
+                glib::ControlFlow::Continue
+            },
+        ));
     }
 }
+
+fn tooltip_text(format: &str, value: f64) -> String {
+    format.replace("{}", &format!("{:.0}", value))
+}
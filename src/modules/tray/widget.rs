@@ -1,20 +1,25 @@
 // ============ modules/tray/widget.rs ============
 use crate::modules::tray::watcher::start_tray_watcher;
+use crate::modules::{Module, ModuleContext};
+use crate::shared::SourceHandles;
 use dbus::Message;
 use dbus::arg::{RefArg, Variant};
 use dbus::blocking::{BlockingSender, Connection};
 use dbus::message::MatchRule;
 use gtk4 as gtk;
 use gtk4::gio;
-use gtk4::glib;
 use gtk4::prelude::*;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Once};
 use std::time::Duration;
 
 pub struct TrayWidget {
     container: gtk::Box,
+    sources: SourceHandles,
+    // Cleared on teardown to stop the SNI host thread
+    running: Arc<AtomicBool>,
 }
 
 #[derive(Clone)]
@@ -59,14 +64,43 @@ enum TrayUpdate {
 // Shared map of currently registered tray items, keyed by bus name.
 type ItemMap = Arc<Mutex<HashMap<String, TrayItem>>>;
 
+// The watcher owns the registrations of every tray item, so it is started
+// once per process and survives trays being rebuilt.
+static WATCHER: Once = Once::new();
+
+// Every tray widget registers its own host, numbered like `...-<pid>-<n>`.
+static HOST_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+impl Module for TrayWidget {
+    fn from_context(ctx: &ModuleContext) -> Option<Self> {
+        let tray = ctx.instance_config(&ctx.config.tray);
+        let tray_config = TrayConfig {
+            spacing: tray.spacing,
+            icon_size: tray.icon_size,
+        };
+        Some(Self::new(tray_config, ctx.orientation))
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        self.container.clone().upcast()
+    }
+
+    fn teardown(&self) {
+        self.running.store(false, Ordering::Relaxed);
+        self.sources.remove_all();
+    }
+}
+
 impl TrayWidget {
     pub fn new(config: TrayConfig, widget_orientation: gtk::Orientation) -> Self {
-        std::thread::spawn(|| {
-            let _ = start_tray_watcher();
-        });
+        WATCHER.call_once(|| {
+            std::thread::spawn(|| {
+                let _ = start_tray_watcher();
+            });
 
-        // Give the watcher a moment to acquire its D-Bus name before we connect.
-        std::thread::sleep(Duration::from_millis(200));
+            // Give the watcher a moment to acquire its D-Bus name before we connect.
+            std::thread::sleep(Duration::from_millis(200));
+        });
 
         let container = gtk::Box::new(widget_orientation, config.spacing);
         container.add_css_class("tray");
@@ -76,45 +110,50 @@ impl TrayWidget {
         let items_ui = Arc::clone(&items);
 
         let (tx, rx) = mpsc::channel::<TrayUpdate>();
-        Self::start_sni_host(items, tx);
+        let running = Arc::new(AtomicBool::new(true));
+        Self::start_sni_host(items, tx, Arc::clone(&running));
 
         let container_clone = container.clone();
         let config_clone = config.clone();
 
-        glib::timeout_add_local(Duration::from_millis(100), move || {
-            while let Ok(update) = rx.try_recv() {
-                match update {
-                    TrayUpdate::Add(item) => {
-                        Self::add_tray_button(&container_clone, &item, &config_clone);
-                    }
-                    TrayUpdate::Remove(bus_name) => {
-                        Self::remove_tray_button(&container_clone, &bus_name);
-                    }
-                    TrayUpdate::UpdateIcon(bus_name) => {
-                        if let Some(path) = Self::lookup_path(&items_ui, &bus_name) {
-                            Self::refresh_icon(
-                                &container_clone,
-                                &bus_name,
-                                &path,
-                                config_clone.icon_size,
-                            );
+        let sources = SourceHandles::default();
+        sources.push(glib::timeout_add_local(
+            Duration::from_millis(100),
+            move || {
+                while let Ok(update) = rx.try_recv() {
+                    match update {
+                        TrayUpdate::Add(item) => {
+                            Self::add_tray_button(&container_clone, &item, &config_clone);
                         }
-                    }
-                    TrayUpdate::UpdateStatus(bus_name) => {
-                        if let Some(path) = Self::lookup_path(&items_ui, &bus_name) {
-                            Self::refresh_status(&container_clone, &bus_name, &path);
+                        TrayUpdate::Remove(bus_name) => {
+                            Self::remove_tray_button(&container_clone, &bus_name);
+                        }
+                        TrayUpdate::UpdateIcon(bus_name) => {
+                            if let Some(path) = Self::lookup_path(&items_ui, &bus_name) {
+                                Self::refresh_icon(
+                                    &container_clone,
+                                    &bus_name,
+                                    &path,
+                                    config_clone.icon_size,
+                                );
+                            }
+                        }
+                        TrayUpdate::UpdateStatus(bus_name) => {
+                            if let Some(path) = Self::lookup_path(&items_ui, &bus_name) {
+                                Self::refresh_status(&container_clone, &bus_name, &path);
+                            }
                         }
                     }
                 }
-            }
-            glib::ControlFlow::Continue
-        });
-
-        Self { container }
-    }
+                glib::ControlFlow::Continue
+            },
+        ));
 
-    pub fn widget(&self) -> &gtk::Box {
-        &self.container
+        Self {
+            container,
+            sources,
+            running,
+        }
     }
 
     fn lookup_path(items: &ItemMap, bus_name: &str) -> Option<String> {
@@ -125,7 +164,7 @@ impl TrayWidget {
             .map(|i| i.object_path.clone())
     }
 
-    fn start_sni_host(items: ItemMap, tx: mpsc::Sender<TrayUpdate>) {
+    fn start_sni_host(items: ItemMap, tx: mpsc::Sender<TrayUpdate>, running: Arc<AtomicBool>) {
         std::thread::spawn(move || {
             let conn = match Connection::new_session() {
                 Ok(c) => c,
@@ -138,8 +177,9 @@ impl TrayWidget {
             // Register ourselves as a StatusNotifierHost so the watcher knows
             // to send us item registrations.
             let pid = std::process::id();
-            let host_bus = format!("org.kde.StatusNotifierHost-{}-0", pid);
-            let host_path = "/StatusNotifierHost/0".to_string();
+            let host_id = HOST_COUNT.fetch_add(1, Ordering::Relaxed);
+            let host_bus = format!("org.kde.StatusNotifierHost-{}-{}", pid, host_id);
+            let host_path = format!("/StatusNotifierHost/{}", host_id);
 
             if let Err(e) = conn.request_name(&host_bus, false, false, false) {
                 eprintln!("[tray] couldn't acquire host name: {}", e);
@@ -249,7 +289,7 @@ impl TrayWidget {
                 }
             }
 
-            loop {
+            while running.load(Ordering::Relaxed) {
                 if let Err(e) = conn.process(Duration::from_secs(1)) {
                     eprintln!("[tray] event loop error: {}", e);
                     break;
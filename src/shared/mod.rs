@@ -1,4 +1,7 @@
 // ============ shared/mod.rs ============
+pub mod signals;
+pub mod sources;
 pub mod util;
 
+pub use sources::*;
 pub use util::*;
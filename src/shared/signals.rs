@@ -0,0 +1,65 @@
+// ============ shared/signals.rs ============
+use once_cell::sync::Lazy;
+use std::sync::{Mutex, mpsc};
+
+/// Polling loop waiting for `SIGRTMIN+signal`.
+struct Subscriber {
+    signal: i32,
+    tx: mpsc::Sender<()>,
+}
+
+static SUBSCRIBERS: Lazy<Mutex<Vec<Subscriber>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn realtime_signals() -> libc::sigset_t {
+    unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        for signal in libc::SIGRTMIN()..=libc::SIGRTMAX() {
+            libc::sigaddset(&mut set, signal);
+        }
+        set
+    }
+}
+
+/// Block the real-time signals so `pkill -RTMIN+N riftbar` no longer kills
+/// the bar but waits for `start_listener`. Threads inherit the mask, so this
+/// has to run before any of them is spawned.
+pub fn block_signals() {
+    let set = realtime_signals();
+    unsafe {
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+    }
+}
+
+/// Wait for real-time signals on a background thread and wake every loop
+/// subscribed to them.
+pub fn start_listener() {
+    std::thread::spawn(|| {
+        let set = realtime_signals();
+        loop {
+            let mut signal = 0;
+            if unsafe { libc::sigwait(&set, &mut signal) } != 0 {
+                continue;
+            }
+
+            let offset = signal - libc::SIGRTMIN();
+            // Drop loops of modules that are gone
+            SUBSCRIBERS
+                .lock()
+                .unwrap()
+                .retain(|subscriber| subscriber.signal != offset || subscriber.tx.send(()).is_ok());
+        }
+    });
+}
+
+/// Send to `tx` whenever `SIGRTMIN+signal` arrives.
+pub fn subscribe(signal: u32, tx: mpsc::Sender<()>) {
+    let max = libc::SIGRTMAX() - libc::SIGRTMIN();
+    match i32::try_from(signal) {
+        Ok(signal) if signal <= max => SUBSCRIBERS.lock().unwrap().push(Subscriber { signal, tx }),
+        _ => eprintln!(
+            "signal = {} is out of range, the maximum is {}",
+            signal, max
+        ),
+    }
+}
@@ -0,0 +1,23 @@
+// ============ shared/sources.rs ============
+use std::{cell::RefCell, rc::Rc};
+
+/// glib sources registered by a module, removed again on teardown so that
+/// timers of destroyed widgets stop firing.
+#[derive(Clone, Default)]
+pub struct SourceHandles(Rc<RefCell<Vec<glib::SourceId>>>);
+
+impl SourceHandles {
+    pub fn push(&self, id: glib::SourceId) {
+        self.0.borrow_mut().push(id);
+    }
+
+    pub fn remove_all(&self) {
+        let context = glib::MainContext::default();
+        for id in self.0.borrow_mut().drain(..) {
+            // Sources that already finished on their own are skipped
+            if let Some(source) = context.find_source_by_id(&id) {
+                source.destroy();
+            }
+        }
+    }
+}
@@ -99,17 +99,13 @@ pub fn create_gesture_handler<W: IsA<gtk::Widget>>(gtk_object: &W, gestures: Ges
         let on_click_middle = gestures.on_click_middle.unwrap_or_default();
         let on_click_right = gestures.on_click_right.unwrap_or_default();
         gesture.connect_released(move |gesture, _, _, _| match gesture.current_button() {
-            2 => {
-                if !on_click_middle.is_empty() {
-                    gesture.set_state(EventSequenceState::Claimed);
-                    run_shell_command(&on_click_middle)
-                }
+            2 if !on_click_middle.is_empty() => {
+                gesture.set_state(EventSequenceState::Claimed);
+                run_shell_command(&on_click_middle)
             }
-            3 => {
-                if !on_click_right.is_empty() {
-                    gesture.set_state(EventSequenceState::Claimed);
-                    run_shell_command(&on_click_right);
-                }
+            3 if !on_click_right.is_empty() => {
+                gesture.set_state(EventSequenceState::Claimed);
+                run_shell_command(&on_click_right);
             }
             _ => {}
         });